@@ -1,5 +1,5 @@
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
-use cw_secret_network_butt_migration::msg::{HandleMsg, InitMsg, QueryMsg};
+use cw_secret_network_butt_migration::msg::{HandleAnswer, HandleMsg, InitMsg, QueryAnswer, QueryMsg};
 use cw_secret_network_butt_migration::state::Config;
 use std::env::current_dir;
 use std::fs::create_dir_all;
@@ -13,5 +13,7 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(HandleAnswer), &out_dir);
+    export_schema(&schema_for!(QueryAnswer), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
 }
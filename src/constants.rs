@@ -1,8 +1,36 @@
+// Two days, in seconds
+pub const ADMIN_OPERATION_DELAY: u64 = 172_800;
+pub const ACCRUED_FEE_RECIPIENTS_KEY: &[u8] = b"accrued_fee_recipients";
+pub const ADMIN_OPERATIONS_COUNT_KEY: &[u8] = b"admin_operations_count";
+pub const ALLOWLISTED_ADDRESSES_KEY: &[u8] = b"allowlisted_addresses";
 pub const BLOCK_SIZE: usize = 256;
 pub const CONFIG_KEY: &[u8] = b"config";
+pub const CONTRACT_ADDRESS_KEY: &[u8] = b"contract_address";
+pub const CONTRACT_VERSION: u8 = 2;
+pub const CONTRACT_VERSION_KEY: &[u8] = b"contract_version";
+pub const FEE_WAIVER_ADDRESSES_KEY: &[u8] = b"fee_waiver_addresses";
+pub const FEE_WAIVER_CODE_HASHES_KEY: &[u8] = b"fee_waiver_code_hashes";
+pub const FROZEN_KEY: &[u8] = b"frozen";
+pub const IMPORTING_KEY: &[u8] = b"importing";
+pub const IMPORT_CHECKSUM_KEY: &[u8] = b"import_checksum";
+pub const MAX_MEMO_LENGTH: usize = 128;
+pub const MAX_ORDERS_SCANNED: u128 = 500;
+pub const MAX_PAGE_SIZE: u128 = 100;
+pub const MIGRATION_POSITION_KEY: &[u8] = b"migration_position";
+pub const MIGRATION_TOTAL_KEY: &[u8] = b"migration_total";
 pub const MOCK_AMOUNT: u128 = 1_000_000_000_000;
 pub const MOCK_BUTT_ADDRESS: &str = "mock-butt-address";
 pub const MOCK_TOKEN_ADDRESS: &str = "mock-token-address";
+pub const OPEN_ORDERS_COUNT_KEY: &[u8] = b"open_orders_count";
+pub const PENDING_ADMIN_OPERATIONS_KEY: &[u8] = b"pending_admin_operations";
+pub const PREFIX_ACCRUED_FEES: &[u8] = b"accrued_fees";
+pub const PREFIX_ADDRESS_QUOTA_USAGE: &[u8] = b"address_quota_usage";
+pub const PREFIX_ALLOWLIST: &[u8] = b"allowlist";
+pub const PREFIX_FEE_WAIVERS: &[u8] = b"fee_waivers";
+pub const PREFIX_FEE_WAIVER_CODES: &[u8] = b"fee_waiver_codes";
 pub const PREFIX_ORDERS: &[u8] = b"orders";
 pub const PREFIX_ORDERS_COUNT: &[u8] = b"orders_count";
 pub const PREFIX_REGISTERED_TOKENS: &[u8] = b"registered_tokens";
+pub const RATE_LIMIT_BUCKETS: u64 = 24;
+pub const REGISTERED_TOKEN_ADDRESSES_KEY: &[u8] = b"registered_token_addresses";
+pub const SCRT_DENOM: &str = "uscrt";
@@ -1,20 +1,42 @@
 use crate::constants::{
-    BLOCK_SIZE, CONFIG_KEY, MOCK_AMOUNT, MOCK_BUTT_ADDRESS, MOCK_TOKEN_ADDRESS, PREFIX_ORDERS,
-    PREFIX_ORDERS_COUNT,
+    ADMIN_OPERATION_DELAY, BLOCK_SIZE, CONFIG_KEY, CONTRACT_ADDRESS_KEY, CONTRACT_VERSION,
+    IMPORT_CHECKSUM_KEY, MAX_ORDERS_SCANNED, MIGRATION_POSITION_KEY, MIGRATION_TOTAL_KEY,
+    MOCK_AMOUNT, MOCK_BUTT_ADDRESS, MOCK_TOKEN_ADDRESS, PREFIX_ORDERS, PREFIX_ORDERS_COUNT,
+    SCRT_DENOM,
+};
+use crate::msg::{
+    HandleAnswer, HandleMsg, InitMsg, QueryAnswer, QueryMsg, ReceiveMsg, Snip20HandleMsg,
 };
-use crate::msg::{HandleMsg, InitMsg, QueryAnswer, QueryMsg, ReceiveMsg};
 use crate::state::{
-    read_registered_token, write_registered_token, Config, FillDetail, HumanizedOrder, Order,
-    RegisteredToken, SecretContract,
+    append_registered_token_address, read_accrued_fee_recipients, read_accrued_fees,
+    read_address_quota_usage, read_admin_operations_count, read_allowlisted,
+    read_allowlisted_addresses, read_contract_version, read_fee_waiver, read_fee_waiver_addresses,
+    read_fee_waiver_code, read_fee_waiver_code_hashes, read_frozen, read_importing,
+    read_open_orders_count, read_pending_admin_operations, read_registered_token,
+    read_registered_token_addresses, remove_accrued_fees, remove_allowlisted, remove_fee_waiver,
+    remove_fee_waiver_code, write_accrued_fees, write_address_quota_usage,
+    write_admin_operations_count, write_allowlisted, write_contract_version, write_fee_waiver,
+    write_fee_waiver_code, write_frozen, write_importing, write_open_orders_count,
+    write_pending_admin_operations, write_registered_token, AccruedFee, AddressQuota,
+    AddressQuotaUsage, AdminOperation, Config, ConfigV1, ExecutionFeeTier, ExportedState,
+    FeeRecipient, FeeRecipientAccruedFees, FeeWaiver, FeeWaiverCode, Fill, FillDetail,
+    HumanizedOrder, Order, OrderDestination, OrderFilter, OrderSplit, OrderV1,
+    PendingAdminOperation, PercentageExecutionFee, RateLimit, RateLimitPeriod, RegisteredToken,
+    RegisteredTokenV1, RelayerOrder, SecretContract, TokenAmount, TokenStats,
+};
+use crate::validations::{
+    authorize, validate_destination, validate_human_addr, validate_memo, validate_page_size,
+    validate_uint128,
 };
-use crate::validations::{authorize, validate_human_addr, validate_uint128};
 use cosmwasm_std::{
-    from_binary, to_binary, Api, BalanceResponse, BankMsg, BankQuery, Binary, CanonicalAddr, Coin,
-    CosmosMsg, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier, QueryRequest,
-    ReadonlyStorage, StdError, StdResult, Storage, Uint128,
+    from_binary, log, to_binary, to_vec, Api, BalanceResponse, BankMsg, BankQuery, Binary,
+    CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse, HumanAddr, InitResponse,
+    LogAttribute, Querier, QueryRequest, ReadonlyStorage, StdError, StdResult, Storage, Uint128,
+    WasmMsg,
 };
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 
+use secret_toolkit::crypto::sha_256;
 use secret_toolkit::snip20;
 use secret_toolkit::storage::{TypedStore, TypedStoreMut};
 
@@ -25,14 +47,29 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<InitResponse> {
     let mut config_store = TypedStoreMut::attach(&mut deps.storage);
     let config: Config = Config {
-        admin: env.message.sender,
+        admin: env.message.sender.clone(),
+        allowlist_enabled: msg.allowlist_enabled.unwrap_or(false),
+        burn_tokens: msg.burn_tokens.unwrap_or(false),
         butt: msg.butt,
         mount_doom: msg.mount_doom,
         execution_fee: msg.execution_fee,
+        fee_recipients: vec![FeeRecipient {
+            address: env.message.sender,
+            weight: 1,
+        }],
+        scrt_execution_fees_held: Uint128(0),
         sscrt: msg.sscrt,
-        total_sent_to_mount_doom: Uint128(0),
+        transfer_memos_enabled: msg.transfer_memos_enabled.unwrap_or(true),
+        relayer: msg.relayer,
     };
     config_store.store(CONFIG_KEY, &config)?;
+    write_contract_version(&mut deps.storage, CONTRACT_VERSION)?;
+    write_importing(&mut deps.storage, msg.importing.unwrap_or(false))?;
+    // Queries don't have access to env, so the contract address is kept for them
+    TypedStoreMut::<CanonicalAddr, _>::attach(&mut deps.storage).store(
+        CONTRACT_ADDRESS_KEY,
+        &deps.api.canonical_address(&env.contract.address)?,
+    )?;
 
     Ok(InitResponse {
         messages: vec![],
@@ -45,12 +82,67 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
+    // Stored orders are read as version 1 orders until the migration is done,
+    // so nothing else can be handled in the meantime
+    if read_contract_version(&deps.storage) < CONTRACT_VERSION {
+        match msg {
+            HandleMsg::MigrateState { .. } => {}
+            _ => return Err(StdError::generic_err("Contract state is being migrated.")),
+        }
+    }
+    // Tokens can't be received and orders can't be changed until the import is finalised
+    if read_importing(&deps.storage) {
+        match msg {
+            HandleMsg::FinaliseImport {}
+            | HandleMsg::ImportDestinations { .. }
+            | HandleMsg::ImportState { .. }
+            | HandleMsg::RegisterTokens { .. } => {}
+            _ => return Err(StdError::generic_err("Contract is importing state.")),
+        }
+    }
+    // Escrow has been moved to a new instance, so nothing can be handled
+    if read_frozen(&deps.storage) {
+        return Err(StdError::generic_err("Contract is frozen."));
+    }
+
     match msg {
+        HandleMsg::AddFeeWaiverCodes { fee_waiver_codes } => {
+            add_fee_waiver_codes(deps, &env, fee_waiver_codes)
+        }
+        HandleMsg::AddFeeWaivers { fee_waivers } => add_fee_waivers(deps, &env, fee_waivers),
+        HandleMsg::AddToAllowlist { addresses } => add_to_allowlist(deps, &env, addresses),
+        HandleMsg::CancelAdminOperation { id } => cancel_admin_operation(deps, &env, id),
         HandleMsg::CancelOrder { position } => cancel_order(deps, &env, position.u128()),
         HandleMsg::ChangeOrdersToProcessing { order_positions } => {
             change_orders_to_processing(deps, &env, order_positions)
         }
+        HandleMsg::ExecuteAdminOperation { id, key } => {
+            execute_admin_operation(deps, &env, id, key)
+        }
         HandleMsg::FillOrders { fill_details } => fill_orders(deps, &env, fill_details),
+        HandleMsg::FinaliseImport {} => finalise_import(deps, &env),
+        HandleMsg::ImportDestinations { destinations } => {
+            import_destinations(deps, &env, destinations)
+        }
+        HandleMsg::ImportState {
+            config,
+            registered_tokens,
+            state,
+            orders,
+            checksum,
+        } => import_state(
+            deps,
+            &env,
+            config,
+            registered_tokens,
+            state,
+            orders,
+            checksum,
+        ),
+        HandleMsg::MigrateState { limit } => migrate_state(deps, &env, limit.u128()),
+        HandleMsg::QueueAdminOperation { operation } => {
+            queue_admin_operation(deps, &env, operation)
+        }
         HandleMsg::Receive {
             from, amount, msg, ..
         } => receive(deps, env, from, amount, msg),
@@ -58,12 +150,41 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             tokens,
             viewing_key,
         } => register_tokens(deps, &env, tokens, viewing_key),
-        HandleMsg::RescueTokens {
-            denom,
-            key,
+        HandleMsg::RemoveFeeWaiverCodes { code_hashes } => {
+            remove_fee_waiver_codes(deps, &env, code_hashes)
+        }
+        HandleMsg::RemoveFeeWaivers { addresses } => remove_fee_waivers(deps, &env, addresses),
+        HandleMsg::RemoveFromAllowlist { addresses } => {
+            remove_from_allowlist(deps, &env, addresses)
+        }
+        HandleMsg::SetExecutionFeeForOrder { owner } => {
+            pad_response(set_execution_fee_for_order_with_scrt(deps, &env, owner))
+        }
+        HandleMsg::UpdateConfig {
+            allowlist_enabled,
+            transfer_memos_enabled,
+        } => update_config(deps, &env, allowlist_enabled, transfer_memos_enabled),
+        HandleMsg::UpdateOrderDestination { position, to } => {
+            update_order_destination(deps, &env, position.u128(), to)
+        }
+        HandleMsg::UpdateRegisteredToken {
+            token_address,
+            enabled,
+            minimum_amount,
+            mount_doom,
+            rate_limit,
+            address_quota,
+        } => update_registered_token(
+            deps,
+            &env,
             token_address,
-        } => rescue_tokens(deps, &env, denom, key, token_address),
-        HandleMsg::UpdateConfig { execution_fee } => update_config(deps, &env, execution_fee),
+            enabled,
+            minimum_amount,
+            mount_doom,
+            rate_limit,
+            address_quota,
+        ),
+        HandleMsg::WithdrawFees {} => withdraw_fees(deps, &env),
     }
 }
 
@@ -72,16 +193,108 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
+        QueryMsg::AccruedFees { address } => to_binary(&QueryAnswer::AccruedFees {
+            accrued_fees: read_accrued_fees(&deps.storage, &deps.api.canonical_address(&address)?)?,
+        }),
         QueryMsg::Config {} => {
             let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY)?;
             Ok(to_binary(&config)?)
         }
+        QueryMsg::ContractVersion {} => to_binary(&QueryAnswer::ContractVersion {
+            version: read_contract_version(&deps.storage),
+        }),
+        QueryMsg::ExecutionFee {
+            token_address,
+            amount,
+        } => {
+            let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY)?;
+            let token: RegisteredToken =
+                registered_token(&deps.storage, &deps.api.canonical_address(&token_address)?)?;
+            to_binary(&QueryAnswer::ExecutionFee {
+                execution_fee: token.execution_fee(config.execution_fee, amount),
+                token_execution_fee: token
+                    .percentage_execution_fee
+                    .map(|percentage_execution_fee| percentage_execution_fee.execution_fee(amount)),
+            })
+        }
+        QueryMsg::ExportState {
+            key,
+            start,
+            limit,
+            checksum,
+        } => export_state(deps, key, start.u128(), limit.u128(), checksum),
+        QueryMsg::FilteredOrders {
+            address,
+            key,
+            filter,
+            ascending,
+            start_after,
+            limit,
+        } => filtered_orders(
+            deps,
+            address,
+            key,
+            filter,
+            ascending.unwrap_or(false),
+            start_after.map(|start_after| start_after.u128()),
+            limit.u128(),
+        ),
+        QueryMsg::MigrationStats {} => {
+            let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY)?;
+            let mut tokens: Vec<TokenStats> = vec![];
+            for token_address in read_registered_token_addresses(&deps.storage) {
+                tokens.push(
+                    registered_token(&deps.storage, &deps.api.canonical_address(&token_address)?)?
+                        .stats(),
+                );
+            }
+            to_binary(&QueryAnswer::MigrationStats {
+                open_orders: Uint128(read_open_orders_count(&deps.storage)),
+                execution_fee: config.execution_fee,
+                tokens,
+            })
+        }
+        QueryMsg::OrderHistory {
+            address,
+            key,
+            start_after,
+            limit,
+        } => order_history(
+            deps,
+            address,
+            key,
+            start_after.map(|start_after| start_after.u128()),
+            limit.u128(),
+        ),
         QueryMsg::Orders {
             address,
             key,
             page,
             page_size,
         } => orders(deps, address, key, page.u128(), page_size.u128()),
+        QueryMsg::PendingAdminOperations {} => to_binary(&QueryAnswer::PendingAdminOperations {
+            pending_admin_operations: read_pending_admin_operations(&deps.storage),
+            delay: ADMIN_OPERATION_DELAY,
+        }),
+        QueryMsg::RegisteredToken { token_address } => {
+            let registered_token: RegisteredToken =
+                registered_token(&deps.storage, &deps.api.canonical_address(&token_address)?)?;
+            Ok(to_binary(&registered_token)?)
+        }
+        QueryMsg::RelayerOrders {
+            key,
+            filter,
+            ascending,
+            start_after,
+            limit,
+        } => relayer_orders(
+            deps,
+            key,
+            filter,
+            ascending.unwrap_or(false),
+            start_after.map(|start_after| start_after.u128()),
+            limit.u128(),
+        ),
     }
 }
 
@@ -94,10 +307,27 @@ fn receive<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<HandleResponse> {
     let msg: ReceiveMsg = from_binary(&msg)?;
     let response = match msg {
-        ReceiveMsg::SetExecutionFeeForOrder {} => {
-            set_execution_fee_for_order(deps, &env, from, amount)
+        // The owner of an order created on their behalf is its creator,
+        // so they can cancel it and receive the refund
+        ReceiveMsg::SetExecutionFeeForOrder { owner } => {
+            set_execution_fee_for_order(deps, &env, owner.unwrap_or(from), amount, false)
         }
-        ReceiveMsg::CreateOrder { to } => create_order(deps, &env, from, amount, to),
+        ReceiveMsg::CreateOrder {
+            to,
+            fee_waiver_code,
+            owner,
+            memo,
+        } => create_order(
+            deps,
+            &env,
+            from.clone(),
+            owner.unwrap_or(from),
+            amount,
+            to,
+            fee_waiver_code,
+            memo,
+        ),
+        ReceiveMsg::CreateOrders { splits } => create_orders(deps, &env, from, amount, splits),
     };
     pad_response(response)
 }
@@ -107,18 +337,16 @@ fn set_execution_fee_for_order<S: Storage, A: Api, Q: Querier>(
     env: &Env,
     from: HumanAddr,
     amount: Uint128,
+    in_scrt: bool,
 ) -> StdResult<HandleResponse> {
-    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-    validate_human_addr(
-        &config.sscrt.address,
-        &env.message.sender,
-        "Execution fee token must be SSCRT.",
-    )?;
-    validate_uint128(
-        config.execution_fee,
-        amount,
-        "Amount sent in must equal execution fee.",
-    )?;
+    let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    if !in_scrt {
+        validate_human_addr(
+            &config.sscrt.address,
+            &env.message.sender,
+            "Execution fee token must be SSCRT.",
+        )?;
+    }
 
     let contract_canonical_address: CanonicalAddr =
         deps.api.canonical_address(&env.contract.address)?;
@@ -132,6 +360,53 @@ fn set_execution_fee_for_order<S: Storage, A: Api, Q: Querier>(
     };
     let mut creator_order =
         order_at_position(&deps.storage, &user_canonical_address, order_position)?;
+    if creator_order.fee_exempt {
+        return Err(StdError::generic_err("Order is exempt from execution fee."));
+    }
+    if creator_order.token_execution_fee.is_some() {
+        return Err(StdError::generic_err(
+            "Execution fee is taken from the order amount.",
+        ));
+    }
+    let token: RegisteredToken = registered_token(
+        &deps.storage,
+        &deps.api.canonical_address(&creator_order.token)?,
+    )?;
+    let execution_fee: Uint128 = apply_fee_discount(
+        token.execution_fee(config.execution_fee, creator_order.amount),
+        creator_order.fee_discount_basis_points,
+    );
+    // Earlier orders created in the same block without an execution fee, such as those
+    // created together from one send, are paid for along with the latest order
+    let mut earlier_creator_orders: Vec<(Order, Uint128)> = vec![];
+    let mut total_execution_fee: Uint128 = execution_fee;
+    for position in (0..order_position).rev() {
+        let earlier_creator_order: Order =
+            order_at_position(&deps.storage, &user_canonical_address, position)?;
+        if earlier_creator_order.created_at_block_height != env.block.height
+            || earlier_creator_order.status != 0
+            || earlier_creator_order.execution_fee.is_some()
+            || earlier_creator_order.token_execution_fee.is_some()
+            || earlier_creator_order.fee_exempt
+        {
+            break;
+        }
+        let earlier_token: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&earlier_creator_order.token)?,
+        )?;
+        let earlier_execution_fee: Uint128 = apply_fee_discount(
+            earlier_token.execution_fee(config.execution_fee, earlier_creator_order.amount),
+            earlier_creator_order.fee_discount_basis_points,
+        );
+        total_execution_fee += earlier_execution_fee;
+        earlier_creator_orders.push((earlier_creator_order, earlier_execution_fee));
+    }
+    validate_uint128(
+        total_execution_fee,
+        amount,
+        "Amount sent in must equal execution fee.",
+    )?;
     validate_uint128(
         Uint128::from(creator_order.created_at_block_height),
         Uint128::from(env.block.height),
@@ -147,7 +422,21 @@ fn set_execution_fee_for_order<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::generic_err("Order is not open."));
     }
 
-    creator_order.execution_fee = Some(amount);
+    creator_order.execution_fee = Some(execution_fee);
+    creator_order.execution_fee_in_scrt = in_scrt;
+    for (mut earlier_creator_order, earlier_execution_fee) in earlier_creator_orders {
+        earlier_creator_order.execution_fee = Some(earlier_execution_fee);
+        earlier_creator_order.execution_fee_in_scrt = in_scrt;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            earlier_creator_order,
+            &contract_canonical_address,
+        )?;
+    }
+    if in_scrt {
+        config.scrt_execution_fees_held += amount;
+        TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+    }
     update_creator_order_and_associated_contract_order(
         &mut deps.storage,
         creator_order.clone(),
@@ -156,11 +445,36 @@ fn set_execution_fee_for_order<S: Storage, A: Api, Q: Querier>(
 
     Ok(HandleResponse {
         messages: vec![],
-        log: vec![],
+        log: vec![
+            log("action", "set_execution_fee_for_order"),
+            log("creator_position", creator_order.position),
+            log("contract_position", creator_order.other_storage_position),
+            log("fee", total_execution_fee),
+        ],
         data: Some(to_binary(&creator_order.into_humanized(&deps.api)?)?),
     })
 }
 
+fn set_execution_fee_for_order_with_scrt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    if env.message.sent_funds.len() != 1 || env.message.sent_funds[0].denom != SCRT_DENOM {
+        return Err(StdError::generic_err(
+            "Execution fee must be sent in uscrt only.",
+        ));
+    }
+
+    set_execution_fee_for_order(
+        deps,
+        env,
+        owner.unwrap_or_else(|| env.message.sender.clone()),
+        env.message.sent_funds[0].amount,
+        true,
+    )
+}
+
 fn set_count<S: Storage>(
     store: &mut S,
     for_address: &CanonicalAddr,
@@ -172,6 +486,116 @@ fn set_count<S: Storage>(
     count_store.store(for_address.as_slice(), &count)
 }
 
+// Add each fee recipient's share of the amount to what they have accrued of the token
+fn accrue_fees<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    fee_recipients: &[FeeRecipient],
+    token: Option<HumanAddr>,
+    amount: Uint128,
+) -> StdResult<()> {
+    for (fee_recipient, share) in fee_shares(fee_recipients, amount) {
+        let fee_recipient_canonical: CanonicalAddr = api.canonical_address(&fee_recipient)?;
+        let mut accrued_fees: Vec<AccruedFee> =
+            read_accrued_fees(storage, &fee_recipient_canonical)?;
+        match accrued_fees
+            .iter_mut()
+            .find(|accrued_fee| accrued_fee.token == token)
+        {
+            Some(accrued_fee) => accrued_fee.amount += share,
+            None => accrued_fees.push(AccruedFee {
+                token: token.clone(),
+                amount: share,
+            }),
+        }
+        write_accrued_fees(storage, &fee_recipient_canonical, &accrued_fees)?;
+    }
+
+    Ok(())
+}
+
+fn add_fee_waiver_codes<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    fee_waiver_codes: Vec<FeeWaiverCode>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for fee_waiver_code in fee_waiver_codes.iter() {
+        validate_discount_basis_points(fee_waiver_code.discount_basis_points)?;
+        write_fee_waiver_code(
+            &mut deps.storage,
+            fee_waiver_code.code_hash.as_slice(),
+            fee_waiver_code.discount_basis_points,
+        )?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "add_fee_waiver_codes")],
+        data: None,
+    })
+}
+
+fn add_fee_waivers<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    fee_waivers: Vec<FeeWaiver>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for fee_waiver in fee_waivers.iter() {
+        validate_discount_basis_points(fee_waiver.discount_basis_points)?;
+        write_fee_waiver(
+            &mut deps.storage,
+            &deps.api.canonical_address(&fee_waiver.address)?,
+            fee_waiver.discount_basis_points,
+        )?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "add_fee_waivers")],
+        data: None,
+    })
+}
+
+fn add_to_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    addresses: Vec<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for address in addresses.iter() {
+        write_allowlisted(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "add_to_allowlist")],
+        data: None,
+    })
+}
+
+fn admin_operation_name(operation: &AdminOperation) -> &'static str {
+    match operation {
+        AdminOperation::ChangeAdmin { .. } => "change_admin",
+        AdminOperation::ChangeMountDoom { .. } => "change_mount_doom",
+        AdminOperation::ChangeRelayer { .. } => "change_relayer",
+        AdminOperation::Freeze { .. } => "freeze",
+        AdminOperation::RescueTokens { .. } => "rescue_tokens",
+        AdminOperation::UpdateBurnTokens { .. } => "update_burn_tokens",
+        AdminOperation::UpdateExecutionFee { .. } => "update_execution_fee",
+        AdminOperation::UpdateExecutionFeeTiers { .. } => "update_execution_fee_tiers",
+        AdminOperation::UpdateFeeRecipients { .. } => "update_fee_recipients",
+        AdminOperation::UpdatePercentageExecutionFee { .. } => "update_percentage_execution_fee",
+    }
+}
+
 fn append_order<S: Storage>(
     store: &mut S,
     order: &Order,
@@ -193,6 +617,67 @@ fn append_order<S: Storage>(
     )
 }
 
+fn apply_fee_discount(execution_fee: Uint128, discount_basis_points: u16) -> Uint128 {
+    Uint128(
+        execution_fee.u128()
+            - execution_fee
+                .multiply_ratio(discount_basis_points, 10_000u128)
+                .u128(),
+    )
+}
+
+// Users authenticate with their viewing key for butt.
+// The contract's own orders are only listed for the relayer, through relayer_orders.
+fn authenticate_orders_query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: &HumanAddr,
+    key: String,
+) -> StdResult<CanonicalAddr> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    query_balance_of_token(deps, address.clone(), config.butt, key)?;
+    let address_canonical: CanonicalAddr = deps.api.canonical_address(address)?;
+    let contract_address: Option<CanonicalAddr> =
+        TypedStore::attach(&deps.storage).may_load(CONTRACT_ADDRESS_KEY)?;
+    if contract_address.as_ref() == Some(&address_canonical) {
+        return Err(StdError::generic_err(
+            "Contract orders can only be queried by the relayer.",
+        ));
+    }
+
+    Ok(address_canonical)
+}
+
+fn cancel_admin_operation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    id: Uint128,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    let mut pending_admin_operations: Vec<PendingAdminOperation> =
+        read_pending_admin_operations(&deps.storage);
+    let index: usize = pending_admin_operations
+        .iter()
+        .position(|pending_admin_operation| pending_admin_operation.id == id)
+        .ok_or_else(|| StdError::generic_err("Admin operation not found."))?;
+    let pending_admin_operation: PendingAdminOperation = pending_admin_operations.remove(index);
+    write_pending_admin_operations(&mut deps.storage, &pending_admin_operations)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "cancel_admin_operation"),
+            log("id", id),
+            log(
+                "operation",
+                admin_operation_name(&pending_admin_operation.operation),
+            ),
+        ],
+        data: None,
+    })
+}
+
 fn cancel_order<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
@@ -211,20 +696,68 @@ fn cancel_order<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
-    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let token_address_canonical: CanonicalAddr =
+        deps.api.canonical_address(&creator_order.token)?;
+    let mut token: RegisteredToken = registered_token(&deps.storage, &token_address_canonical)?;
+    // The refunded amount no longer counts towards the rate limit or the creator's quota
+    if let Some(rate_limit) = token.rate_limit.clone() {
+        rate_limit.period.release(
+            &mut token.rate_limit_buckets,
+            rate_limit.period.now(
+                creator_order.created_at_block_height,
+                creator_order.created_at_block_time,
+            ),
+            creator_order.amount,
+        );
+        write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+    }
+    let mut quota_usage: AddressQuotaUsage = read_address_quota_usage(
+        &deps.storage,
+        &creator_order.sender,
+        &token_address_canonical,
+    );
+    quota_usage.total = Uint128(
+        quota_usage
+            .total
+            .u128()
+            .saturating_sub(creator_order.amount.u128()),
+    );
+    if let Some(period) = quota_usage.period.clone() {
+        period.release(
+            &mut quota_usage.buckets,
+            period.now(
+                creator_order.created_at_block_height,
+                creator_order.created_at_block_time,
+            ),
+            creator_order.amount,
+        );
+    }
+    write_address_quota_usage(
+        &mut deps.storage,
+        &creator_order.sender,
+        &token_address_canonical,
+        &quota_usage,
+    )?;
     // Send refund to the creator
     let mut messages: Vec<CosmosMsg> = vec![];
-    messages.push(snip20::transfer_msg(
+    messages.push(transfer_msg(
         env.message.sender.clone(),
         creator_order.amount,
-        None,
-        BLOCK_SIZE,
-        config.butt.contract_hash,
-        config.butt.address,
+        transfer_memo(
+            &config,
+            format!("Refund for cancelled order {}", creator_order.position),
+        ),
+        token.contract_hash,
+        token.address,
     )?);
 
     // Update Txs
     creator_order.status = 3;
+    write_open_orders_count(
+        &mut deps.storage,
+        read_open_orders_count(&deps.storage).saturating_sub(1),
+    )?;
     update_creator_order_and_associated_contract_order(
         &mut deps.storage,
         creator_order.clone(),
@@ -233,19 +766,46 @@ fn cancel_order<S: Storage, A: Api, Q: Querier>(
 
     // If order has an execution fee send it back to the user
     if let Some(execution_fee_unwrapped) = creator_order.execution_fee {
-        messages.push(snip20::transfer_msg(
-            env.message.sender.clone(),
-            execution_fee_unwrapped,
-            None,
-            BLOCK_SIZE,
-            config.sscrt.contract_hash,
-            config.sscrt.address,
-        )?);
+        if creator_order.execution_fee_in_scrt {
+            config.scrt_execution_fees_held =
+                (config.scrt_execution_fees_held - execution_fee_unwrapped)?;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: env.message.sender.clone(),
+                amount: vec![Coin {
+                    denom: SCRT_DENOM.to_string(),
+                    amount: execution_fee_unwrapped,
+                }],
+            }));
+        } else {
+            messages.push(transfer_msg(
+                env.message.sender.clone(),
+                execution_fee_unwrapped,
+                transfer_memo(
+                    &config,
+                    format!(
+                        "Execution fee refund for cancelled order {}",
+                        creator_order.position
+                    ),
+                ),
+                config.sscrt.contract_hash,
+                config.sscrt.address,
+            )?);
+        }
     }
 
     pad_response(Ok(HandleResponse {
         messages,
-        log: vec![],
+        log: vec![
+            log("action", "cancel_order"),
+            log("creator_position", creator_order.position),
+            log("contract_position", creator_order.other_storage_position),
+            log("previous_status", status_name(0)),
+            log("status", status_name(creator_order.status)),
+            log("amount", creator_order.amount),
+            log("fee", creator_order.execution_fee.unwrap_or(Uint128(0))),
+        ],
         data: Some(to_binary(&creator_order.into_humanized(&deps.api)?)?),
     }))
 }
@@ -256,89 +816,406 @@ fn change_orders_to_processing<S: Storage, A: Api, Q: Querier>(
     order_positions: Vec<Uint128>,
 ) -> StdResult<HandleResponse> {
     let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-    authorize(vec![config.admin], &env.message.sender)?;
+    authorize(config.operators(), &env.message.sender)?;
 
-    // Store order
+    let mut orders: Vec<RelayerOrder> = vec![];
+    let mut open_orders_count: u128 = read_open_orders_count(&deps.storage);
+    let mut logs: Vec<LogAttribute> = vec![log("action", "change_orders_to_processing")];
     let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
     for order_position in order_positions.iter() {
         let contract_order =
             order_at_position(&deps.storage, &contract_address, order_position.u128())?;
-        if contract_order.status == 0 && contract_order.execution_fee.is_some() {
+        if contract_order.status == 0
+            && (contract_order.execution_fee.is_some()
+                || contract_order.token_execution_fee.is_some()
+                || contract_order.fee_exempt)
+        {
             let creator_order_position: Uint128 = contract_order.other_storage_position;
             let mut creator_order = contract_order;
             creator_order.position = creator_order_position;
             creator_order.other_storage_position = *order_position;
             creator_order.status = 1;
+            open_orders_count = open_orders_count.saturating_sub(1);
+            logs.push(log("creator_position", creator_order_position));
+            logs.push(log("contract_position", order_position));
+            logs.push(log("previous_status", status_name(0)));
+            logs.push(log("status", status_name(creator_order.status)));
             update_creator_order_and_associated_contract_order(
                 &mut deps.storage,
-                creator_order,
+                creator_order.clone(),
                 &contract_address,
             )?;
+            let mut contract_order: Order = creator_order;
+            contract_order.position = *order_position;
+            contract_order.other_storage_position = creator_order_position;
+            orders.push(contract_order.into_relayer_order());
         }
     }
 
-    Ok(HandleResponse {
+    write_open_orders_count(&mut deps.storage, open_orders_count)?;
+
+    pad_response(Ok(HandleResponse {
         messages: vec![],
-        log: vec![],
-        data: None,
-    })
+        log: logs,
+        data: Some(to_binary(&HandleAnswer::ChangeOrdersToProcessing {
+            orders,
+        })?),
+    }))
+}
+
+// Running checksum over orders, continuing from the checksum of the orders before them
+fn checksum_orders(previous_checksum: &[u8], orders: &[Order]) -> StdResult<Vec<u8>> {
+    let mut checksum: Vec<u8> = previous_checksum.to_vec();
+    for order in orders.iter() {
+        checksum.extend(to_vec(order)?);
+        checksum = sha_256(&checksum).to_vec();
+    }
+
+    Ok(checksum)
+}
+
+// Counts an upgraded or imported order towards its sender's quota usage
+fn count_order_towards_quota<S: Storage, A: Api>(
+    storage: &mut S,
+    api: &A,
+    order: &Order,
+) -> StdResult<()> {
+    if order.status == 3 {
+        return Ok(());
+    }
+    let token_address_canonical: CanonicalAddr = api.canonical_address(&order.token)?;
+    let mut quota_usage: AddressQuotaUsage =
+        read_address_quota_usage(storage, &order.sender, &token_address_canonical);
+    quota_usage.total += order.amount;
+    write_address_quota_usage(
+        storage,
+        &order.sender,
+        &token_address_canonical,
+        &quota_usage,
+    )
 }
 
 // activity (0 => open, 1 => processing, 2 => filled, 3 => cancelled)
+#[allow(clippy::too_many_arguments)]
 fn create_order<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
     from: HumanAddr,
+    owner: HumanAddr,
     amount: Uint128,
     to: HumanAddr,
+    fee_waiver_code: Option<String>,
+    memo: Option<String>,
 ) -> StdResult<HandleResponse> {
-    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-    authorize(vec![config.butt.address], &env.message.sender)?;
+    let order: Order = store_order(deps, env, from, owner, amount, to, fee_waiver_code, memo)?;
 
-    // Store order
-    let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
-    let creator_address: CanonicalAddr = deps.api.canonical_address(&from)?;
-    let contract_order_position =
-        storage_count(&deps.storage, &contract_address, PREFIX_ORDERS_COUNT)?;
-    let creator_order_position =
-        storage_count(&deps.storage, &creator_address, PREFIX_ORDERS_COUNT)?;
-    // Store contract order first
-    let mut order = Order {
-        position: Uint128(contract_order_position),
-        execution_fee: None,
-        other_storage_position: Uint128(creator_order_position),
-        creator: creator_address.clone(),
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [vec![log("action", "create_order")], order_logs(&order)].concat(),
+        data: Some(to_binary(&order.into_humanized(&deps.api)?)?),
+    })
+}
+
+// Split one send into an order for each split
+fn create_orders<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    splits: Vec<OrderSplit>,
+) -> StdResult<HandleResponse> {
+    if splits.is_empty() {
+        return Err(StdError::generic_err("Splits must be present."));
+    }
+    let mut amount_split: u128 = 0;
+    for split in splits.iter() {
+        amount_split = amount_split
+            .checked_add(split.amount.u128())
+            .ok_or_else(|| StdError::generic_err("Split amounts must add up to amount."))?;
+    }
+    validate_uint128(
+        Uint128(amount_split),
         amount,
-        to,
-        status: 0,
-        azero_transaction_hash: None,
-        created_at_block_time: env.block.time,
-        created_at_block_height: env.block.height,
-    };
-    append_order(&mut deps.storage, &order, &contract_address)?;
-    // Store creator order next
-    order.position = Uint128(creator_order_position);
-    order.other_storage_position = Uint128(contract_order_position);
-    append_order(&mut deps.storage, &order, &creator_address)?;
+        "Split amounts must add up to amount.",
+    )?;
+
+    let mut logs: Vec<LogAttribute> = vec![log("action", "create_orders")];
+    let mut orders: Vec<HumanizedOrder> = vec![];
+    for split in splits {
+        let order: Order = store_order(
+            deps,
+            env,
+            from.clone(),
+            from.clone(),
+            split.amount,
+            split.to,
+            None,
+            None,
+        )?;
+        logs.extend(order_logs(&order));
+        orders.push(order.into_humanized(&deps.api)?);
+    }
 
     Ok(HandleResponse {
         messages: vec![],
-        log: vec![],
-        data: Some(to_binary(&order.into_humanized(&deps.api)?)?),
+        log: logs,
+        data: Some(to_binary(&orders)?),
     })
 }
 
-fn fill_orders<S: Storage, A: Api, Q: Querier>(
+fn execute_admin_operation<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
-    fill_details: Vec<FillDetail>,
+    id: Uint128,
+    key: Option<String>,
 ) -> StdResult<HandleResponse> {
     let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
     authorize(vec![config.admin.clone()], &env.message.sender)?;
 
-    let mut messages = vec![];
-    let mut amount_to_send_to_admin: Uint128 = Uint128(0);
-    let mut amount_to_send_to_mount_doom: Uint128 = Uint128(0);
+    let mut pending_admin_operations: Vec<PendingAdminOperation> =
+        read_pending_admin_operations(&deps.storage);
+    let index: usize = pending_admin_operations
+        .iter()
+        .position(|pending_admin_operation| pending_admin_operation.id == id)
+        .ok_or_else(|| StdError::generic_err("Admin operation not found."))?;
+    if env.block.time < pending_admin_operations[index].executable_at_block_time {
+        return Err(StdError::generic_err(format!(
+            "Admin operation can't be executed until {}.",
+            pending_admin_operations[index].executable_at_block_time
+        )));
+    }
+    let pending_admin_operation: PendingAdminOperation = pending_admin_operations.remove(index);
+    write_pending_admin_operations(&mut deps.storage, &pending_admin_operations)?;
+
+    let mut logs: Vec<LogAttribute> = vec![
+        log("action", "execute_admin_operation"),
+        log("id", id),
+        log(
+            "operation",
+            admin_operation_name(&pending_admin_operation.operation),
+        ),
+    ];
+    let mut response: HandleResponse = match pending_admin_operation.operation {
+        AdminOperation::ChangeAdmin { admin } => {
+            config.admin = admin;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::ChangeMountDoom {
+            token_address,
+            mount_doom,
+        } => {
+            let token_address_canonical: CanonicalAddr =
+                deps.api.canonical_address(&token_address)?;
+            let mut token: RegisteredToken =
+                registered_token(&deps.storage, &token_address_canonical)?;
+            token.mount_doom = Some(mount_doom.clone());
+            write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+            // BUTT's Mount Doom is also kept in the config
+            if token_address == config.butt.address {
+                config.mount_doom = mount_doom;
+                TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            }
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::ChangeRelayer { relayer } => {
+            config.relayer = relayer;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::Freeze { new_instance } => freeze(deps, env, new_instance, key)?,
+        AdminOperation::RescueTokens {
+            denom,
+            token_address,
+        } => rescue_tokens(deps, env, denom, key, token_address)?,
+        AdminOperation::UpdateBurnTokens { burn_tokens } => {
+            config.burn_tokens = burn_tokens;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::UpdateExecutionFee { execution_fee } => {
+            config.execution_fee = execution_fee;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::UpdateExecutionFeeTiers {
+            token_address,
+            execution_fee_tiers,
+        } => update_execution_fee_tiers(deps, token_address, execution_fee_tiers)?,
+        AdminOperation::UpdateFeeRecipients { fee_recipients } => {
+            config.fee_recipients = fee_recipients;
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: None,
+            }
+        }
+        AdminOperation::UpdatePercentageExecutionFee {
+            token_address,
+            percentage_execution_fee,
+        } => update_percentage_execution_fee(deps, token_address, percentage_execution_fee)?,
+    };
+    logs.append(&mut response.log);
+    response.log = logs;
+
+    Ok(response)
+}
+
+// Config, registered tokens, the rest of the state and the contract copies of orders,
+// oldest first. Creator copies and order counts can be rebuilt from the contract copies.
+// Only frozen contracts can be exported, so that nothing changes once the export has started.
+// Destinations are left out so that the admin can't read them.
+fn export_state<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    key: String,
+    start: u128,
+    limit: u128,
+    checksum: Option<Binary>,
+) -> StdResult<Binary> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    // The admin's viewing key for butt is used to authenticate the admin
+    query_balance_of_token(deps, config.admin.clone(), config.butt.clone(), key)?;
+    if !read_frozen(&deps.storage) {
+        return Err(StdError::generic_err(
+            "Contract must be frozen before its state is exported.",
+        ));
+    }
+
+    let mut registered_tokens: Vec<RegisteredToken> = vec![];
+    for token_address in read_registered_token_addresses(&deps.storage) {
+        registered_tokens.push(registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&token_address)?,
+        )?);
+    }
+    let mut accrued_fees: Vec<FeeRecipientAccruedFees> = vec![];
+    for fee_recipient in read_accrued_fee_recipients(&deps.storage) {
+        accrued_fees.push(FeeRecipientAccruedFees {
+            fee_recipient: deps.api.human_address(&fee_recipient)?,
+            accrued_fees: read_accrued_fees(&deps.storage, &fee_recipient)?,
+        });
+    }
+    let mut allowlist: Vec<HumanAddr> = vec![];
+    for address in read_allowlisted_addresses(&deps.storage) {
+        allowlist.push(deps.api.human_address(&address)?);
+    }
+    let mut fee_waivers: Vec<FeeWaiver> = vec![];
+    for address in read_fee_waiver_addresses(&deps.storage) {
+        if let Some(discount_basis_points) = read_fee_waiver(&deps.storage, &address) {
+            fee_waivers.push(FeeWaiver {
+                address: deps.api.human_address(&address)?,
+                discount_basis_points,
+            });
+        }
+    }
+    let mut fee_waiver_codes: Vec<FeeWaiverCode> = vec![];
+    for code_hash in read_fee_waiver_code_hashes(&deps.storage) {
+        if let Some(discount_basis_points) = read_fee_waiver_code(&deps.storage, &code_hash) {
+            fee_waiver_codes.push(FeeWaiverCode {
+                code_hash: Binary(code_hash),
+                discount_basis_points,
+            });
+        }
+    }
+    let state: ExportedState = ExportedState {
+        accrued_fees,
+        admin_operations_count: Uint128(read_admin_operations_count(&deps.storage)),
+        allowlist,
+        fee_waiver_codes,
+        fee_waivers,
+        pending_admin_operations: read_pending_admin_operations(&deps.storage),
+    };
+    let contract_address: CanonicalAddr =
+        TypedStore::<CanonicalAddr, _>::attach(&deps.storage).load(CONTRACT_ADDRESS_KEY)?;
+    let total: u128 = storage_count(&deps.storage, &contract_address, PREFIX_ORDERS_COUNT)?;
+    let end: u128 = total.min(start.saturating_add(limit));
+    let mut orders: Vec<Order> = vec![];
+    for position in start.min(end)..end {
+        let mut order: Order = order_at_position(&deps.storage, &contract_address, position)?;
+        order.to = HumanAddr::default();
+        orders.push(order);
+    }
+    let previous_checksum: Vec<u8> = checksum.map(|c| c.0).unwrap_or_default();
+
+    to_binary(&QueryAnswer::ExportState {
+        config,
+        contract_version: read_contract_version(&deps.storage),
+        registered_tokens,
+        state,
+        checksum: Binary(checksum_orders(&previous_checksum, &orders)?),
+        orders,
+        total: Uint128(total),
+    })
+}
+
+// Split amount between fee recipients in proportion to their weights.
+// The rounding dust goes to the first fee recipient and zero shares are left out.
+fn fee_shares(fee_recipients: &[FeeRecipient], amount: Uint128) -> Vec<(HumanAddr, Uint128)> {
+    if amount.is_zero() {
+        return vec![];
+    }
+
+    let total_weight: u128 = fee_recipients
+        .iter()
+        .map(|fee_recipient| u128::from(fee_recipient.weight))
+        .sum();
+    let mut shares: Vec<(HumanAddr, Uint128)> = fee_recipients
+        .iter()
+        .map(|fee_recipient| {
+            (
+                fee_recipient.address.clone(),
+                amount.multiply_ratio(fee_recipient.weight, total_weight),
+            )
+        })
+        .collect();
+    let amount_shared: u128 = shares.iter().map(|(_, share)| share.u128()).sum();
+    shares[0].1 += Uint128(amount.u128() - amount_shared);
+    shares
+        .into_iter()
+        .filter(|(_, share)| !share.is_zero())
+        .collect()
+}
+
+fn fill_orders<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    fill_details: Vec<FillDetail>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(config.operators(), &env.message.sender)?;
+
+    let mut messages = vec![];
+    let mut logs: Vec<LogAttribute> = vec![log("action", "fill_orders")];
+    let mut orders: Vec<RelayerOrder> = vec![];
+    let mut burned: Vec<TokenAmount> = vec![];
+    let mut sent_to_mount_doom: Vec<TokenAmount> = vec![];
+    let mut fees_accrued: Vec<AccruedFee> = vec![];
+    let mut amount_to_send_to_fee_recipients: Uint128 = Uint128(0);
+    let mut scrt_amount_to_send_to_fee_recipients: Uint128 = Uint128(0);
+    // Amounts, execution fees taken from them and the fills they're for are grouped by token,
+    // so that each token only needs one burn or transfer for each
+    let mut amounts_filled: Vec<(HumanAddr, Uint128, Uint128, Vec<String>)> = vec![];
     // Store order
     let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
     for fill_detail in fill_details.iter() {
@@ -352,442 +1229,4494 @@ fn fill_orders<S: Storage, A: Api, Q: Querier>(
             let mut creator_order = contract_order;
             creator_order.position = creator_order_position;
             creator_order.other_storage_position = fill_detail.position;
-            creator_order.status = 2;
-            creator_order.azero_transaction_hash = Some(fill_detail.azero_transaction_hash.clone());
+            let unfilled_amount: Uint128 = (creator_order.amount - creator_order.filled_amount)?;
+            let fill_amount: Uint128 = fill_detail.amount.unwrap_or(unfilled_amount);
+            if fill_amount.is_zero() {
+                return Err(StdError::generic_err(
+                    "Fill amount must be greater than zero.",
+                ));
+            }
+            if fill_amount > unfilled_amount {
+                return Err(StdError::generic_err(
+                    "Fill amount must be less than or equal to unfilled amount.",
+                ));
+            }
+            let filled_amount_before: Uint128 = creator_order.filled_amount;
+            creator_order.filled_amount += fill_amount;
+            creator_order.fills.push(Fill {
+                amount: fill_amount,
+                azero_transaction_hash: fill_detail.azero_transaction_hash.clone(),
+            });
+            // Order stays processing until its full amount is filled
+            if creator_order.filled_amount == creator_order.amount {
+                creator_order.status = 2;
+                creator_order.azero_transaction_hash =
+                    Some(fill_detail.azero_transaction_hash.clone());
+            }
             update_creator_order_and_associated_contract_order(
                 &mut deps.storage,
                 creator_order.clone(),
                 &contract_address,
             )?;
-            amount_to_send_to_mount_doom += creator_order.amount;
-            amount_to_send_to_admin += creator_order.execution_fee.unwrap();
+            let mut contract_order: Order = creator_order.clone();
+            contract_order.position = fill_detail.position;
+            contract_order.other_storage_position = creator_order_position;
+            orders.push(contract_order.into_relayer_order());
+            let token_execution_fee: Uint128 = creator_order.pro_rated(
+                creator_order.token_execution_fee.unwrap_or(Uint128(0)),
+                filled_amount_before,
+            );
+            let amount_to_migrate: Uint128 = (fill_amount - token_execution_fee)?;
+            logs.push(log("creator_position", creator_order_position));
+            logs.push(log("contract_position", fill_detail.position));
+            logs.push(log("previous_status", status_name(1)));
+            logs.push(log("status", status_name(creator_order.status)));
+            logs.push(log("amount", fill_amount));
+            logs.push(log(
+                "azero_transaction_hash",
+                &fill_detail.azero_transaction_hash,
+            ));
+            let fill_reference: String = format!(
+                "{} ({})",
+                fill_detail.position, fill_detail.azero_transaction_hash
+            );
+            match amounts_filled
+                .iter_mut()
+                .find(|(token_address, _, _, _)| *token_address == creator_order.token)
+            {
+                Some((_, amount, execution_fee, fill_references)) => {
+                    *amount += amount_to_migrate;
+                    *execution_fee += token_execution_fee;
+                    fill_references.push(fill_reference);
+                }
+                None => amounts_filled.push((
+                    creator_order.token.clone(),
+                    amount_to_migrate,
+                    token_execution_fee,
+                    vec![fill_reference],
+                )),
+            }
+            if !token_execution_fee.is_zero() {
+                logs.push(log("fee", token_execution_fee));
+            }
+            if let Some(execution_fee) = creator_order.execution_fee {
+                let execution_fee: Uint128 =
+                    creator_order.pro_rated(execution_fee, filled_amount_before);
+                logs.push(log("fee", execution_fee));
+                if creator_order.execution_fee_in_scrt {
+                    scrt_amount_to_send_to_fee_recipients += execution_fee;
+                } else {
+                    amount_to_send_to_fee_recipients += execution_fee;
+                }
+            }
         }
     }
-    if !amount_to_send_to_mount_doom.is_zero() {
-        config.total_sent_to_mount_doom += amount_to_send_to_mount_doom;
-        TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
-        messages.push(snip20::transfer_msg(
-            config.mount_doom.address.clone(),
-            amount_to_send_to_mount_doom,
-            None,
-            BLOCK_SIZE,
-            config.butt.contract_hash.clone(),
-            config.butt.address.clone(),
-        )?);
-    }
-    if !amount_to_send_to_admin.is_zero() {
-        messages.push(snip20::transfer_msg(
-            config.admin,
-            amount_to_send_to_admin,
-            None,
-            BLOCK_SIZE,
-            config.sscrt.contract_hash,
-            config.sscrt.address,
-        )?);
-    }
-
-    Ok(HandleResponse {
-        messages,
-        log: vec![],
-        data: None,
-    })
-}
-
-fn get_orders<A: Api, S: ReadonlyStorage>(
-    api: &A,
-    storage: &S,
-    for_address: &CanonicalAddr,
-    page: u128,
-    page_size: u128,
-) -> StdResult<(Vec<HumanizedOrder>, u128)> {
-    let total: u128 = storage_count(storage, for_address, PREFIX_ORDERS_COUNT)?;
-    let offset: u128 = page * page_size;
-    let end = total - offset;
-    let start = end.saturating_sub(page_size);
-    let store =
-        ReadonlyPrefixedStorage::multilevel(&[PREFIX_ORDERS, for_address.as_slice()], storage);
-    let mut orders: Vec<HumanizedOrder> = Vec::new();
-    let store = TypedStore::<Order, _>::attach(&store);
-    for position in (start..end).rev() {
-        orders.push(store.load(&position.to_le_bytes())?.into_humanized(api)?);
+    for (token_address, amount_filled, token_execution_fee, fill_references) in amounts_filled {
+        let token_address_canonical: CanonicalAddr = deps.api.canonical_address(&token_address)?;
+        let mut token: RegisteredToken = registered_token(&deps.storage, &token_address_canonical)?;
+        if config.burn_tokens {
+            token.total_burned += amount_filled;
+            burned.push(TokenAmount {
+                token: token_address.clone(),
+                amount: amount_filled,
+            });
+            messages.push(snip20::burn_msg(
+                amount_filled,
+                None,
+                BLOCK_SIZE,
+                token.contract_hash.clone(),
+                token.address.clone(),
+            )?);
+        } else {
+            let mount_doom: SecretContract = token
+                .mount_doom
+                .clone()
+                .ok_or_else(|| StdError::generic_err("Mount Doom is not set for token."))?;
+            token.total_sent_to_mount_doom += amount_filled;
+            sent_to_mount_doom.push(TokenAmount {
+                token: token_address.clone(),
+                amount: amount_filled,
+            });
+            messages.push(transfer_msg(
+                mount_doom.address,
+                amount_filled,
+                transfer_memo(
+                    &config,
+                    format!("Migration of orders {}", fill_references.join(", ")),
+                ),
+                token.contract_hash.clone(),
+                token.address.clone(),
+            )?);
+        }
+        accrue_fees(
+            &mut deps.storage,
+            &deps.api,
+            &config.fee_recipients,
+            Some(token_address.clone()),
+            token_execution_fee,
+        )?;
+        fees_accrued.push(AccruedFee {
+            token: Some(token_address),
+            amount: token_execution_fee,
+        });
+        write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
     }
+    // Execution fees are held by the contract until fee recipients withdraw them
+    accrue_fees(
+        &mut deps.storage,
+        &deps.api,
+        &config.fee_recipients,
+        Some(config.sscrt.address.clone()),
+        amount_to_send_to_fee_recipients,
+    )?;
+    accrue_fees(
+        &mut deps.storage,
+        &deps.api,
+        &config.fee_recipients,
+        None,
+        scrt_amount_to_send_to_fee_recipients,
+    )?;
 
-    Ok((orders, total))
-}
-
-fn order_at_position<S: Storage>(
-    store: &S,
-    address: &CanonicalAddr,
-    position: u128,
-) -> StdResult<Order> {
-    let store = ReadonlyPrefixedStorage::multilevel(&[PREFIX_ORDERS, address.as_slice()], store);
-    // Try to access the storage of orders for the account.
-    // If it doesn't exist yet, return an empty list of transfers.
-    let store = TypedStore::<Order, _>::attach(&store);
+    fees_accrued.push(AccruedFee {
+        token: Some(config.sscrt.address),
+        amount: amount_to_send_to_fee_recipients,
+    });
+    fees_accrued.push(AccruedFee {
+        token: None,
+        amount: scrt_amount_to_send_to_fee_recipients,
+    });
+    fees_accrued.retain(|accrued_fee| !accrued_fee.amount.is_zero());
 
-    store.load(&position.to_le_bytes())
+    pad_response(Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: Some(to_binary(&HandleAnswer::FillOrders {
+            orders,
+            burned,
+            sent_to_mount_doom,
+            fees_accrued,
+        })?),
+    }))
 }
 
-fn orders<S: Storage, A: Api, Q: Querier>(
+fn filtered_orders<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     address: HumanAddr,
     key: String,
-    page: u128,
-    page_size: u128,
+    filter: OrderFilter,
+    ascending: bool,
+    start_after: Option<u128>,
+    limit: u128,
 ) -> StdResult<Binary> {
-    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-    // This is here so that the user can use their viewing key for butt for this
-    query_balance_of_token(deps, address.clone(), config.butt, key)?;
+    validate_page_size(limit)?;
+    let address: CanonicalAddr = authenticate_orders_query(deps, &address, key)?;
 
-    let (orders, total) = get_orders(
-        &deps.api,
+    let (orders, next_cursor) = get_filtered_orders(
         &deps.storage,
-        &deps.api.canonical_address(&address)?,
-        page,
-        page_size,
+        &address,
+        &filter,
+        ascending,
+        start_after,
+        limit,
     )?;
 
-    let result = QueryAnswer::Orders {
-        orders,
-        total: Some(Uint128(total)),
+    let result = QueryAnswer::FilteredOrders {
+        orders: orders
+            .into_iter()
+            .map(|order| order.into_humanized(&deps.api))
+            .collect::<StdResult<Vec<HumanizedOrder>>>()?,
+        next_cursor: next_cursor.map(Uint128),
     };
-    to_binary(&result)
-}
-
-fn pad_response(response: StdResult<HandleResponse>) -> StdResult<HandleResponse> {
-    response.map(|mut response| {
-        response.data = response.data.map(|mut data| {
-            space_pad(BLOCK_SIZE, &mut data.0);
-            data
-        });
-        response
-    })
-}
-
-fn query_balance_of_token<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    address: HumanAddr,
-    token: SecretContract,
-    viewing_key: String,
-) -> StdResult<Uint128> {
-    if token.address == HumanAddr::from(MOCK_TOKEN_ADDRESS)
-        || token.address == HumanAddr::from(MOCK_BUTT_ADDRESS)
-    {
-        Ok(Uint128(MOCK_AMOUNT))
-    } else {
-        let balance = snip20::balance_query(
-            &deps.querier,
-            address,
-            viewing_key,
-            BLOCK_SIZE,
-            token.contract_hash,
-            token.address,
-        )?;
-        Ok(balance.amount)
-    }
+    let mut result_binary: Binary = to_binary(&result)?;
+    space_pad(BLOCK_SIZE, &mut result_binary.0);
+    Ok(result_binary)
 }
 
-fn register_tokens<S: Storage, A: Api, Q: Querier>(
+fn finalise_import<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
-    tokens: Vec<SecretContract>,
-    viewing_key: String,
 ) -> StdResult<HandleResponse> {
     let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
     authorize(vec![config.admin], &env.message.sender)?;
-    let mut messages = vec![];
-    for token in tokens {
-        let token_address_canonical = deps.api.canonical_address(&token.address)?;
-        let token_details: Option<RegisteredToken> =
-            read_registered_token(&deps.storage, &token_address_canonical);
-        if token_details.is_none() {
-            let token_details: RegisteredToken = RegisteredToken {
-                address: token.address.clone(),
-                contract_hash: token.contract_hash.clone(),
-            };
-            write_registered_token(&mut deps.storage, &token_address_canonical, &token_details)?;
-            messages.push(snip20::register_receive_msg(
-                env.contract_code_hash.clone(),
-                None,
-                BLOCK_SIZE,
-                token.contract_hash.clone(),
-                token.address.clone(),
-            )?);
-        }
-        messages.push(snip20::set_viewing_key_msg(
-            viewing_key.clone(),
-            None,
-            BLOCK_SIZE,
-            token.contract_hash,
-            token.address,
-        )?);
+    if !read_importing(&deps.storage) {
+        return Err(StdError::generic_err("Contract is not importing state."));
     }
 
+    write_importing(&mut deps.storage, false)?;
+
     Ok(HandleResponse {
-        messages,
-        log: vec![],
+        messages: vec![],
+        log: vec![log("action", "finalise_import")],
         data: None,
     })
 }
 
-fn rescue_tokens<S: Storage, A: Api, Q: Querier>(
+// Executed through a queued admin operation.
+// Frozen contracts can't handle anything, so that their state can be exported and imported into
+// the new instance. Every SCRT and registered token balance is moved to the new instance,
+// which covers escrowed tokens, execution fees held for orders and accrued fees.
+fn freeze<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
-    denom: Option<String>,
+    new_instance: HumanAddr,
     key: Option<String>,
-    token_address: Option<HumanAddr>,
 ) -> StdResult<HandleResponse> {
-    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-    authorize(vec![config.admin.clone()], &env.message.sender)?;
+    let key: String =
+        key.ok_or_else(|| StdError::generic_err("Key is required to move tokens."))?;
+    write_frozen(&mut deps.storage)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    if let Some(denom_unwrapped) = denom {
-        let balance_response: BalanceResponse =
-            deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
-                address: env.contract.address.clone(),
-                denom: denom_unwrapped,
-            }))?;
-
-        let withdrawal_coins: Vec<Coin> = vec![balance_response.amount];
+    let mut coins: Vec<Coin> = vec![];
+    let balance_response: BalanceResponse =
+        deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
+            address: env.contract.address.clone(),
+            denom: SCRT_DENOM.to_string(),
+        }))?;
+    // Sending a zero coin would fail the whole transaction
+    if !balance_response.amount.amount.is_zero() {
+        coins.push(balance_response.amount);
         messages.push(CosmosMsg::Bank(BankMsg::Send {
             from_address: env.contract.address.clone(),
-            to_address: config.admin.clone(),
-            amount: withdrawal_coins,
+            to_address: new_instance.clone(),
+            amount: coins.clone(),
         }));
     }
-
-    if let Some(token_address_unwrapped) = token_address {
-        if token_address_unwrapped == config.butt.address {
-            return Err(StdError::generic_err("BUTT can't be rescued."));
+    // sSCRT can only have been received as execution fees if it is registered
+    let mut tokens: Vec<TokenAmount> = vec![];
+    for token_address in read_registered_token_addresses(&deps.storage) {
+        let token: RegisteredToken =
+            registered_token(&deps.storage, &deps.api.canonical_address(&token_address)?)?;
+        let balance: Uint128 = query_balance_of_token(
+            deps,
+            env.contract.address.clone(),
+            SecretContract {
+                address: token.address.clone(),
+                contract_hash: token.contract_hash.clone(),
+            },
+            key.clone(),
+        )?;
+        if balance.is_zero() {
+            continue;
         }
 
-        if let Some(key_unwrapped) = key {
-            let registered_token: RegisteredToken = read_registered_token(
-                &deps.storage,
-                &deps.api.canonical_address(&token_address_unwrapped)?,
-            )
-            .unwrap();
-            let balance: Uint128 = query_balance_of_token(
-                deps,
-                env.contract.address.clone(),
-                SecretContract {
-                    address: token_address_unwrapped,
-                    contract_hash: registered_token.contract_hash.clone(),
-                },
-                key_unwrapped,
-            )?;
-            messages.push(snip20::transfer_msg(
-                config.admin,
-                balance,
-                None,
-                BLOCK_SIZE,
-                registered_token.contract_hash,
-                registered_token.address,
-            )?)
-        }
+        tokens.push(TokenAmount {
+            token: token.address.clone(),
+            amount: balance,
+        });
+        messages.push(snip20::transfer_msg(
+            new_instance.clone(),
+            balance,
+            None,
+            BLOCK_SIZE,
+            token.contract_hash,
+            token.address,
+        )?);
     }
 
-    Ok(HandleResponse {
+    pad_response(Ok(HandleResponse {
         messages,
-        log: vec![],
-        data: None,
-    })
+        log: vec![log("new_instance", &new_instance)],
+        data: Some(to_binary(&HandleAnswer::Freeze { coins, tokens })?),
+    }))
 }
 
-// Take a Vec<u8> and pad it up to a multiple of `block_size`, using spaces at the end.
-fn space_pad(block_size: usize, message: &mut Vec<u8>) -> &mut Vec<u8> {
-    let len = message.len();
-    let surplus = len % block_size;
-    if surplus == 0 {
-        return message;
+// Scans at most MAX_ORDERS_SCANNED positions after start_after for orders matching the filter.
+// When there are positions left to scan, the last position scanned is returned as the cursor.
+fn get_filtered_orders<S: ReadonlyStorage>(
+    storage: &S,
+    for_address: &CanonicalAddr,
+    filter: &OrderFilter,
+    ascending: bool,
+    start_after: Option<u128>,
+    limit: u128,
+) -> StdResult<(Vec<Order>, Option<u128>)> {
+    let total: u128 = storage_count(storage, for_address, PREFIX_ORDERS_COUNT)?;
+    let positions: Vec<u128> = if ascending {
+        let start: u128 = start_after.map_or(0, |start_after| start_after.saturating_add(1));
+        (start..total).take(MAX_ORDERS_SCANNED as usize).collect()
+    } else {
+        let end: u128 = start_after.map_or(total, |start_after| start_after.min(total));
+        (0..end).rev().take(MAX_ORDERS_SCANNED as usize).collect()
+    };
+    let store =
+        ReadonlyPrefixedStorage::multilevel(&[PREFIX_ORDERS, for_address.as_slice()], storage);
+    let store = TypedStore::<Order, _>::attach(&store);
+    let mut orders: Vec<Order> = vec![];
+    let mut last_position_scanned: Option<u128> = None;
+    for position in positions {
+        if orders.len() as u128 >= limit {
+            break;
+        }
+        let order: Order = store.load(&position.to_le_bytes())?;
+        if filter.matches(&order) {
+            orders.push(order);
+        }
+        last_position_scanned = Some(position);
     }
+    let next_cursor: Option<u128> = last_position_scanned.filter(|position| {
+        if ascending {
+            position + 1 < total
+        } else {
+            *position > 0
+        }
+    });
 
-    let missing = block_size - surplus;
-    message.reserve(missing);
-    message.extend(std::iter::repeat(b' ').take(missing));
-    message
+    Ok((orders, next_cursor))
 }
 
-fn storage_count<S: ReadonlyStorage>(
-    store: &S,
+fn get_orders<A: Api, S: ReadonlyStorage>(
+    api: &A,
+    storage: &S,
     for_address: &CanonicalAddr,
-    storage_prefix: &[u8],
-) -> StdResult<u128> {
-    let store = ReadonlyPrefixedStorage::new(storage_prefix, store);
-    let store = TypedStore::<u128, _>::attach(&store);
-    let position: Option<u128> = store.may_load(for_address.as_slice())?;
+    page: u128,
+    page_size: u128,
+) -> StdResult<(Vec<HumanizedOrder>, u128)> {
+    let total: u128 = storage_count(storage, for_address, PREFIX_ORDERS_COUNT)?;
+    let offset: u128 = page.saturating_mul(page_size);
+    let end = total.saturating_sub(offset);
+    let start = end.saturating_sub(page_size);
+    let store =
+        ReadonlyPrefixedStorage::multilevel(&[PREFIX_ORDERS, for_address.as_slice()], storage);
+    let mut orders: Vec<HumanizedOrder> = Vec::new();
+    let store = TypedStore::<Order, _>::attach(&store);
+    for position in (start..end).rev() {
+        orders.push(store.load(&position.to_le_bytes())?.into_humanized(api)?);
+    }
 
-    Ok(position.unwrap_or(0))
+    Ok((orders, total))
 }
 
-fn update_config<S: Storage, A: Api, Q: Querier>(
+// Imports a page of exported state into a fresh instance.
+// Pages must be imported in the order they were exported, so that the running checksum matches.
+// Destinations are left out of exported state, so the relayer imports them from its relayer
+// orders of the frozen instance
+fn import_destinations<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
-    execution_fee: Uint128,
+    destinations: Vec<OrderDestination>,
 ) -> StdResult<HandleResponse> {
-    let mut config_store = TypedStoreMut::attach(&mut deps.storage);
-    let mut config: Config = config_store.load(CONFIG_KEY).unwrap();
-    authorize(vec![config.admin.clone()], &env.message.sender)?;
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let relayer: HumanAddr = config
+        .relayer
+        .ok_or_else(|| StdError::generic_err("Relayer is not set."))?;
+    authorize(vec![relayer], &env.message.sender)?;
+    if !read_importing(&deps.storage) {
+        return Err(StdError::generic_err("Contract is not importing state."));
+    }
 
-    config.execution_fee = execution_fee;
-    config_store.store(CONFIG_KEY, &config)?;
+    let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
+    for destination in destinations.iter() {
+        validate_destination(&destination.to)?;
+        let contract_order: Order = order_at_position(
+            &deps.storage,
+            &contract_address,
+            destination.position.u128(),
+        )?;
+        let creator_order_position: Uint128 = contract_order.other_storage_position;
+        let mut creator_order: Order = contract_order;
+        creator_order.position = creator_order_position;
+        creator_order.other_storage_position = destination.position;
+        creator_order.to = destination.to.clone();
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order,
+            &contract_address,
+        )?;
+    }
 
     Ok(HandleResponse {
         messages: vec![],
-        log: vec![],
+        log: vec![
+            log("action", "import_destinations"),
+            log("destinations_imported", destinations.len()),
+        ],
         data: None,
     })
 }
 
-fn update_creator_order_and_associated_contract_order<S: Storage>(
-    store: &mut S,
-    creator_order: Order,
-    contract_address: &CanonicalAddr,
-) -> StdResult<()> {
-    let mut user_store =
-        PrefixedStorage::multilevel(&[PREFIX_ORDERS, creator_order.creator.as_slice()], store);
-    // Try to access the storage of orders for the account.
-    // If it doesn't exist yet, return an empty list of transfers.
-    let mut user_store = TypedStoreMut::<Order, _, _>::attach(&mut user_store);
-    user_store.store(&creator_order.position.u128().to_le_bytes(), &creator_order)?;
-    let mut contract_store =
-        PrefixedStorage::multilevel(&[PREFIX_ORDERS, contract_address.as_slice()], store);
-    let mut contract_store = TypedStoreMut::<Order, _, _>::attach(&mut contract_store);
-    let contract_order_position: Uint128 = creator_order.other_storage_position;
-    let creator_order_position: Uint128 = creator_order.position;
-    let mut contract_order = creator_order;
-    contract_order.position = contract_order_position;
-    contract_order.other_storage_position = creator_order_position;
-    contract_store.store(
-        &contract_order.position.u128().to_le_bytes(),
-        &contract_order,
-    )?;
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::state::SecretContract;
-    use cosmwasm_std::from_binary;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
-    use cosmwasm_std::StdError::NotFound;
-
-    pub const MOCK_ADMIN: &str = "admin";
-    pub const MOCK_MOUNT_DOOM_ADDRESS: &str = "mock-mount-doom-contract-hash-address";
-    pub const MOCK_VIEWING_KEY: &str = "DELIGHTFUL";
-    pub const MOCK_SSCRT_ADDRESS: &str = "mock-sscrt-address";
-
-    // === HELPERS ===
-    fn create_order_helper<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
-        let receive_msg = ReceiveMsg::CreateOrder {
-            to: mock_token().address,
-        };
-        let handle_msg = HandleMsg::Receive {
-            sender: mock_user_address(),
-            from: mock_user_address(),
-            amount: Uint128(MOCK_AMOUNT),
-            msg: to_binary(&receive_msg).unwrap(),
-        };
-        handle(deps, mock_env(mock_butt().address, &[]), handle_msg.clone()).unwrap();
+fn import_state<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: Option<Config>,
+    registered_tokens: Vec<RegisteredToken>,
+    state: Option<ExportedState>,
+    orders: Vec<Order>,
+    checksum: Binary,
+) -> StdResult<HandleResponse> {
+    let current_config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY)?;
+    authorize(vec![current_config.admin], &env.message.sender)?;
+    // Only instances initialised to import state can import it, and only until it is finalised.
+    // Nothing else can be handled until then, so every order is an imported one.
+    if !read_importing(&deps.storage) {
+        return Err(StdError::generic_err("Contract is not importing state."));
     }
 
-    fn init_helper(
-        register_tokens: bool,
-    ) -> (
-        StdResult<InitResponse>,
-        Extern<MockStorage, MockApi, MockQuerier>,
-    ) {
-        let env = mock_env(MOCK_ADMIN, &[]);
-        let mut deps = mock_dependencies(20, &[]);
-        let msg = InitMsg {
-            butt: mock_butt(),
-            execution_fee: mock_execution_fee(),
-            mount_doom: mock_mount_doom(),
-            sscrt: mock_sscrt(),
-        };
-        let init_result = init(&mut deps, env.clone(), msg);
-        if register_tokens {
-            let handle_msg = HandleMsg::RegisterTokens {
-                tokens: vec![mock_butt(), mock_token()],
-                viewing_key: MOCK_VIEWING_KEY.to_string(),
-            };
-            handle(&mut deps, env, handle_msg.clone()).unwrap();
-        }
-        (init_result, deps)
+    if let Some(mut config) = config {
+        validate_fee_recipients(&config.fee_recipients)?;
+        // SCRT held for orders is moved by the freeze, so no more than has arrived can be held
+        let balance_response: BalanceResponse =
+            deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
+                address: env.contract.address.clone(),
+                denom: SCRT_DENOM.to_string(),
+            }))?;
+        config.scrt_execution_fees_held = Uint128(
+            config
+                .scrt_execution_fees_held
+                .u128()
+                .min(balance_response.amount.amount.u128()),
+        );
+        TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
     }
-
-    fn mock_butt() -> SecretContract {
-        SecretContract {
-            address: HumanAddr::from(MOCK_BUTT_ADDRESS),
-            contract_hash: "mock-butt-contract-hash".to_string(),
+    if let Some(state) = state {
+        for fee_recipient_accrued_fees in state.accrued_fees.iter() {
+            write_accrued_fees(
+                &mut deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&fee_recipient_accrued_fees.fee_recipient)?,
+                &fee_recipient_accrued_fees.accrued_fees,
+            )?;
+        }
+        for address in state.allowlist.iter() {
+            write_allowlisted(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+        }
+        for fee_waiver_code in state.fee_waiver_codes.iter() {
+            validate_discount_basis_points(fee_waiver_code.discount_basis_points)?;
+            write_fee_waiver_code(
+                &mut deps.storage,
+                fee_waiver_code.code_hash.as_slice(),
+                fee_waiver_code.discount_basis_points,
+            )?;
+        }
+        for fee_waiver in state.fee_waivers.iter() {
+            validate_discount_basis_points(fee_waiver.discount_basis_points)?;
+            write_fee_waiver(
+                &mut deps.storage,
+                &deps.api.canonical_address(&fee_waiver.address)?,
+                fee_waiver.discount_basis_points,
+            )?;
         }
+        write_pending_admin_operations(&mut deps.storage, &state.pending_admin_operations)?;
+        write_admin_operations_count(&mut deps.storage, state.admin_operations_count.u128())?;
+    }
+    for token in registered_tokens {
+        write_registered_token(
+            &mut deps.storage,
+            &deps.api.canonical_address(&token.address)?,
+            &token,
+        )?;
+        append_registered_token_address(&mut deps.storage, &token.address)?;
     }
+    let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
+    let mut open_orders_count: u128 = read_open_orders_count(&deps.storage);
+    for order in orders.iter() {
+        let contract_order_position =
+            storage_count(&deps.storage, &contract_address, PREFIX_ORDERS_COUNT)?;
+        let creator_order_position =
+            storage_count(&deps.storage, &order.creator, PREFIX_ORDERS_COUNT)?;
+        if order.position.u128() != contract_order_position
+            || order.other_storage_position.u128() != creator_order_position
+        {
+            return Err(StdError::generic_err(
+                "Orders must be imported in order of position.",
+            ));
+        }
 
-    fn mock_contract() -> SecretContract {
-        let env = mock_env(mock_user_address(), &[]);
-        SecretContract {
-            address: env.contract.address,
-            contract_hash: env.contract_code_hash,
+        append_order(&mut deps.storage, order, &contract_address)?;
+        let mut creator_order: Order = order.clone();
+        creator_order.position = order.other_storage_position;
+        creator_order.other_storage_position = order.position;
+        append_order(&mut deps.storage, &creator_order, &order.creator)?;
+        if order.status == 0 {
+            open_orders_count += 1;
         }
+        count_order_towards_quota(&mut deps.storage, &deps.api, order)?;
     }
-
-    fn mock_execution_fee() -> Uint128 {
-        Uint128(5_555)
+    let previous_checksum: Vec<u8> = TypedStore::<Vec<u8>, _>::attach(&deps.storage)
+        .may_load(IMPORT_CHECKSUM_KEY)?
+        .unwrap_or_default();
+    let new_checksum: Vec<u8> = checksum_orders(&previous_checksum, &orders)?;
+    if new_checksum != checksum.0 {
+        return Err(StdError::generic_err("Checksum does not match."));
     }
+    TypedStoreMut::<Vec<u8>, _>::attach(&mut deps.storage)
+        .store(IMPORT_CHECKSUM_KEY, &new_checksum)?;
+    write_open_orders_count(&mut deps.storage, open_orders_count)?;
 
-    fn mock_mount_doom() -> SecretContract {
-        SecretContract {
-            address: HumanAddr::from(MOCK_MOUNT_DOOM_ADDRESS),
-            contract_hash: "mock-mount-doom-contract-hash".to_string(),
-        }
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "import_state"),
+            log("orders_imported", orders.len()),
+        ],
+        data: None,
+    })
+}
+
+// Upgrade state stored by an earlier version of the contract to the current version.
+// Orders are upgraded up to limit at a time, so that all of them can be upgraded
+// over several transactions.
+fn migrate_state<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    limit: u128,
+) -> StdResult<HandleResponse> {
+    if read_contract_version(&deps.storage) >= CONTRACT_VERSION {
+        return Err(StdError::generic_err(
+            "Contract state is already up to date.",
+        ));
     }
 
-    fn mock_sscrt() -> SecretContract {
-        SecretContract {
-            address: HumanAddr::from(MOCK_SSCRT_ADDRESS),
-            contract_hash: "mock-sscrt-contract-hash".to_string(),
+    // Version 1 is the only earlier version.
+    // Config and registered tokens are upgraded in the first transaction.
+    let migration_position: Option<u128> =
+        TypedStore::<u128, _>::attach(&deps.storage).may_load(MIGRATION_POSITION_KEY)?;
+    let config: Config = match migration_position {
+        Some(_) => TypedStore::attach(&deps.storage).load(CONFIG_KEY)?,
+        None => {
+            let config_v1: ConfigV1 = TypedStore::attach(&deps.storage).load(CONFIG_KEY)?;
+            authorize(vec![config_v1.admin.clone()], &env.message.sender)?;
+            // Only BUTT and SSCRT were registered in version 1
+            let mut token_addresses: Vec<HumanAddr> = vec![config_v1.butt.address.clone()];
+            if config_v1.sscrt.address != config_v1.butt.address {
+                token_addresses.push(config_v1.sscrt.address.clone());
+            }
+            for token_address in token_addresses.iter() {
+                let token_address_canonical: CanonicalAddr =
+                    deps.api.canonical_address(token_address)?;
+                if let Some(token_v1) =
+                    RegisteredTokenV1::read(&deps.storage, &token_address_canonical)?
+                {
+                    write_registered_token(
+                        &mut deps.storage,
+                        &token_address_canonical,
+                        &token_v1.upgrade(&config_v1),
+                    )?;
+                    append_registered_token_address(&mut deps.storage, token_address)?;
+                }
+            }
+            let config: Config = config_v1.upgrade();
+            TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+            TypedStoreMut::<CanonicalAddr, _>::attach(&mut deps.storage).store(
+                CONTRACT_ADDRESS_KEY,
+                &deps.api.canonical_address(&env.contract.address)?,
+            )?;
+            config
         }
-    }
+    };
+    authorize(vec![config.admin.clone()], &env.message.sender)?;
 
-    fn mock_token() -> SecretContract {
-        SecretContract {
-            address: HumanAddr::from(MOCK_TOKEN_ADDRESS),
-            contract_hash: "mock-token-contract-hash".to_string(),
+    // Every order has a contract copy, which points to its creator copy
+    let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
+    // The total is saved in the first transaction so that it stays fixed between transactions
+    let total: u128 = match migration_position {
+        Some(_) => TypedStore::<u128, _>::attach(&deps.storage).load(MIGRATION_TOTAL_KEY)?,
+        None => {
+            let total: u128 = storage_count(&deps.storage, &contract_address, PREFIX_ORDERS_COUNT)?;
+            TypedStoreMut::<u128, _>::attach(&mut deps.storage)
+                .store(MIGRATION_TOTAL_KEY, &total)?;
+            total
+        }
+    };
+    let start: u128 = migration_position.unwrap_or(0);
+    let end: u128 = total.min(start.saturating_add(limit));
+    let mut open_orders_count: u128 = read_open_orders_count(&deps.storage);
+    for position in start..end {
+        let contract_order_v1: OrderV1 = {
+            let store = ReadonlyPrefixedStorage::multilevel(
+                &[PREFIX_ORDERS, contract_address.as_slice()],
+                &deps.storage,
+            );
+            TypedStore::<OrderV1, _>::attach(&store).load(&position.to_le_bytes())?
+        };
+        let mut creator_order: Order = contract_order_v1.upgrade(&config.butt.address);
+        creator_order.position = creator_order.other_storage_position;
+        creator_order.other_storage_position = Uint128(position);
+        if creator_order.status == 0 {
+            open_orders_count += 1;
         }
+        count_order_towards_quota(&mut deps.storage, &deps.api, &creator_order)?;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order,
+            &contract_address,
+        )?;
     }
-
-    fn mock_user_address() -> HumanAddr {
-        HumanAddr::from("gary")
+    write_open_orders_count(&mut deps.storage, open_orders_count)?;
+    if end == total {
+        deps.storage.remove(MIGRATION_POSITION_KEY);
+        deps.storage.remove(MIGRATION_TOTAL_KEY);
+        write_contract_version(&mut deps.storage, CONTRACT_VERSION)?;
+    } else {
+        TypedStoreMut::<u128, _>::attach(&mut deps.storage).store(MIGRATION_POSITION_KEY, &end)?;
     }
 
-    // === UNIT TESTS ===
-    #[test]
-    fn test_change_orders_to_processing() {
-        let (_init_result, mut deps) = init_helper(true);
-        let mut handle_msg = HandleMsg::ChangeOrdersToProcessing {
-            order_positions: vec![],
-        };
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "migrate_state"),
+            log("migration_position", end),
+        ],
+        data: None,
+    })
+}
 
-        // = when not called by an admin
-        let mut handle_result = handle(
-            &mut deps,
+fn order_at_position<S: Storage>(
+    store: &S,
+    address: &CanonicalAddr,
+    position: u128,
+) -> StdResult<Order> {
+    let store = ReadonlyPrefixedStorage::multilevel(&[PREFIX_ORDERS, address.as_slice()], store);
+    // Try to access the storage of orders for the account.
+    // If it doesn't exist yet, return an empty list of transfers.
+    let store = TypedStore::<Order, _>::attach(&store);
+
+    store.load(&position.to_le_bytes())
+}
+
+// Positions are fixed once orders are stored, so paging by position is stable
+fn order_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+    key: String,
+    start_after: Option<u128>,
+    limit: u128,
+) -> StdResult<Binary> {
+    validate_page_size(limit)?;
+    let address: CanonicalAddr = authenticate_orders_query(deps, &address, key)?;
+
+    // Every order matches the default filter, so a page is never cut short by the scan limit
+    let (orders, next_cursor) = get_filtered_orders(
+        &deps.storage,
+        &address,
+        &OrderFilter::default(),
+        false,
+        start_after,
+        limit,
+    )?;
+
+    let result = QueryAnswer::OrderHistory {
+        orders: orders
+            .into_iter()
+            .map(|order| order.into_humanized(&deps.api))
+            .collect::<StdResult<Vec<HumanizedOrder>>>()?,
+        next_cursor: next_cursor.map(Uint128),
+    };
+    let mut result_binary: Binary = to_binary(&result)?;
+    space_pad(BLOCK_SIZE, &mut result_binary.0);
+    Ok(result_binary)
+}
+
+// Order is the creator's copy. The destination and memo are left out so that they stay private.
+fn order_logs(order: &Order) -> Vec<LogAttribute> {
+    vec![
+        log("creator_position", order.position),
+        log("contract_position", order.other_storage_position),
+        log("status", status_name(order.status)),
+        log("amount", order.amount),
+        log("fee", order.token_execution_fee.unwrap_or(Uint128(0))),
+    ]
+}
+
+fn orders<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+    key: String,
+    page: u128,
+    page_size: u128,
+) -> StdResult<Binary> {
+    validate_page_size(page_size)?;
+    let address: CanonicalAddr = authenticate_orders_query(deps, &address, key)?;
+
+    let (orders, total) = get_orders(&deps.api, &deps.storage, &address, page, page_size)?;
+
+    let result = QueryAnswer::Orders {
+        orders,
+        total: Some(Uint128(total)),
+    };
+    // Pad so that the length of memos isn't revealed by the size of the response
+    let mut result_binary: Binary = to_binary(&result)?;
+    space_pad(BLOCK_SIZE, &mut result_binary.0);
+    Ok(result_binary)
+}
+
+fn pad_response(response: StdResult<HandleResponse>) -> StdResult<HandleResponse> {
+    response.map(|mut response| {
+        response.data = response.data.map(|mut data| {
+            space_pad(BLOCK_SIZE, &mut data.0);
+            data
+        });
+        response
+    })
+}
+
+fn queue_admin_operation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    operation: AdminOperation,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+    // Operations are validated when queued, so that invalid ones don't sit out the delay
+    match &operation {
+        AdminOperation::ChangeMountDoom { token_address, .. } => {
+            registered_token(&deps.storage, &deps.api.canonical_address(token_address)?)?;
+        }
+        AdminOperation::ChangeRelayer {
+            relayer: Some(relayer),
+        } => {
+            deps.api.canonical_address(relayer)?;
+        }
+        AdminOperation::Freeze { new_instance } => {
+            if *new_instance == env.contract.address {
+                return Err(StdError::generic_err(
+                    "New instance must be another contract.",
+                ));
+            }
+            deps.api.canonical_address(new_instance)?;
+        }
+        AdminOperation::UpdateExecutionFeeTiers {
+            token_address,
+            execution_fee_tiers,
+        } => {
+            validate_execution_fee_tiers(execution_fee_tiers)?;
+            registered_token(&deps.storage, &deps.api.canonical_address(token_address)?)?;
+        }
+        AdminOperation::UpdateFeeRecipients { fee_recipients } => {
+            validate_fee_recipients(fee_recipients)?;
+        }
+        AdminOperation::UpdatePercentageExecutionFee {
+            token_address,
+            percentage_execution_fee,
+        } => {
+            if let Some(percentage_execution_fee_unwrapped) = percentage_execution_fee {
+                validate_percentage_execution_fee(percentage_execution_fee_unwrapped)?;
+            }
+            registered_token(&deps.storage, &deps.api.canonical_address(token_address)?)?;
+        }
+        _ => {}
+    }
+
+    let id: u128 = read_admin_operations_count(&deps.storage);
+    let pending_admin_operation = PendingAdminOperation {
+        id: Uint128(id),
+        operation,
+        queued_at_block_time: env.block.time,
+        executable_at_block_time: env.block.time + ADMIN_OPERATION_DELAY,
+    };
+    let mut pending_admin_operations: Vec<PendingAdminOperation> =
+        read_pending_admin_operations(&deps.storage);
+    pending_admin_operations.push(pending_admin_operation.clone());
+    write_pending_admin_operations(&mut deps.storage, &pending_admin_operations)?;
+    write_admin_operations_count(&mut deps.storage, id + 1)?;
+
+    pad_response(Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "queue_admin_operation"),
+            log("id", id),
+            log(
+                "operation",
+                admin_operation_name(&pending_admin_operation.operation),
+            ),
+            log(
+                "executable_at_block_time",
+                pending_admin_operation.executable_at_block_time,
+            ),
+        ],
+        data: Some(to_binary(&HandleAnswer::QueueAdminOperation {
+            pending_admin_operation,
+        })?),
+    }))
+}
+
+fn query_balance_of_token<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+    token: SecretContract,
+    viewing_key: String,
+) -> StdResult<Uint128> {
+    if token.address == HumanAddr::from(MOCK_TOKEN_ADDRESS)
+        || token.address == HumanAddr::from(MOCK_BUTT_ADDRESS)
+    {
+        Ok(Uint128(MOCK_AMOUNT))
+    } else {
+        let balance = snip20::balance_query(
+            &deps.querier,
+            address,
+            viewing_key,
+            BLOCK_SIZE,
+            token.contract_hash,
+            token.address,
+        )?;
+        Ok(balance.amount)
+    }
+}
+
+fn register_tokens<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    tokens: Vec<SecretContract>,
+    viewing_key: String,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin.clone()], &env.message.sender)?;
+    let mut messages = vec![];
+    let mut registered_tokens: Vec<HumanAddr> = vec![];
+    for token in tokens {
+        let token_address_canonical = deps.api.canonical_address(&token.address)?;
+        let token_details: Option<RegisteredToken> =
+            read_registered_token(&deps.storage, &token_address_canonical);
+        if token_details.is_none() {
+            // BUTT is migratable to the configured Mount Doom out of the box
+            let butt: bool = token.address == config.butt.address;
+            let token_details: RegisteredToken = RegisteredToken {
+                address: token.address.clone(),
+                address_quota: None,
+                contract_hash: token.contract_hash.clone(),
+                enabled: butt,
+                execution_fee_tiers: vec![],
+                minimum_amount: Uint128(0),
+                mount_doom: if butt {
+                    Some(config.mount_doom.clone())
+                } else {
+                    None
+                },
+                percentage_execution_fee: None,
+                rate_limit: None,
+                rate_limit_buckets: vec![],
+                total_burned: Uint128(0),
+                total_sent_to_mount_doom: Uint128(0),
+            };
+            write_registered_token(&mut deps.storage, &token_address_canonical, &token_details)?;
+            append_registered_token_address(&mut deps.storage, &token.address)?;
+            registered_tokens.push(token.address.clone());
+            messages.push(snip20::register_receive_msg(
+                env.contract_code_hash.clone(),
+                None,
+                BLOCK_SIZE,
+                token.contract_hash.clone(),
+                token.address.clone(),
+            )?);
+        }
+        messages.push(snip20::set_viewing_key_msg(
+            viewing_key.clone(),
+            None,
+            BLOCK_SIZE,
+            token.contract_hash,
+            token.address,
+        )?);
+    }
+
+    pad_response(Ok(HandleResponse {
+        messages,
+        log: vec![log("action", "register_tokens")],
+        data: Some(to_binary(&HandleAnswer::RegisterTokens {
+            registered_tokens,
+        })?),
+    }))
+}
+
+fn registered_token<S: Storage>(
+    storage: &S,
+    token_address: &CanonicalAddr,
+) -> StdResult<RegisteredToken> {
+    read_registered_token(storage, token_address)
+        .ok_or_else(|| StdError::generic_err("Token is not registered."))
+}
+
+fn relayer_orders<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    key: String,
+    filter: OrderFilter,
+    ascending: bool,
+    start_after: Option<u128>,
+    limit: u128,
+) -> StdResult<Binary> {
+    validate_page_size(limit)?;
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let relayer: HumanAddr = config
+        .relayer
+        .ok_or_else(|| StdError::generic_err("Relayer is not set."))?;
+    query_balance_of_token(deps, relayer, config.butt, key)?;
+
+    let contract_address: CanonicalAddr =
+        TypedStore::attach(&deps.storage).load(CONTRACT_ADDRESS_KEY)?;
+    let (orders, next_cursor) = get_filtered_orders(
+        &deps.storage,
+        &contract_address,
+        &filter,
+        ascending,
+        start_after,
+        limit,
+    )?;
+
+    let result = QueryAnswer::RelayerOrders {
+        orders: orders
+            .into_iter()
+            .map(|order| order.into_relayer_order())
+            .collect(),
+        next_cursor: next_cursor.map(Uint128),
+    };
+    let mut result_binary: Binary = to_binary(&result)?;
+    space_pad(BLOCK_SIZE, &mut result_binary.0);
+    Ok(result_binary)
+}
+
+fn remove_fee_waiver_codes<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    code_hashes: Vec<Binary>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for code_hash in code_hashes.iter() {
+        remove_fee_waiver_code(&mut deps.storage, code_hash.as_slice())?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "remove_fee_waiver_codes")],
+        data: None,
+    })
+}
+
+fn remove_fee_waivers<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    addresses: Vec<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for address in addresses.iter() {
+        remove_fee_waiver(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "remove_fee_waivers")],
+        data: None,
+    })
+}
+
+fn remove_from_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    addresses: Vec<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+
+    for address in addresses.iter() {
+        remove_allowlisted(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "remove_from_allowlist")],
+        data: None,
+    })
+}
+
+// Executed through a queued admin operation
+fn rescue_tokens<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    denom: Option<String>,
+    key: Option<String>,
+    token_address: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut coins: Vec<Coin> = vec![];
+    let mut tokens: Vec<TokenAmount> = vec![];
+    if let Some(denom_unwrapped) = denom {
+        let balance_response: BalanceResponse =
+            deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
+                address: env.contract.address.clone(),
+                denom: denom_unwrapped,
+            }))?;
+
+        let mut withdrawal_coin: Coin = balance_response.amount;
+        // SCRT held as execution fees for orders can't be rescued
+        if withdrawal_coin.denom == SCRT_DENOM {
+            withdrawal_coin.amount = Uint128(
+                withdrawal_coin
+                    .amount
+                    .u128()
+                    .saturating_sub(config.scrt_execution_fees_held.u128()),
+            );
+        }
+        // Sending a zero coin would fail the whole transaction
+        if !withdrawal_coin.amount.is_zero() {
+            let withdrawal_coins: Vec<Coin> = vec![withdrawal_coin];
+            coins.extend(withdrawal_coins.clone());
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: config.admin.clone(),
+                amount: withdrawal_coins,
+            }));
+        }
+    }
+
+    if let Some(token_address_unwrapped) = token_address {
+        if token_address_unwrapped == config.butt.address {
+            return Err(StdError::generic_err("BUTT can't be rescued."));
+        }
+        // SSCRT held as execution fees for orders and accrued fees can't be rescued
+        if token_address_unwrapped == config.sscrt.address {
+            return Err(StdError::generic_err("SSCRT can't be rescued."));
+        }
+
+        if let Some(key_unwrapped) = key {
+            let registered_token: RegisteredToken = registered_token(
+                &deps.storage,
+                &deps.api.canonical_address(&token_address_unwrapped)?,
+            )?;
+            if registered_token.mount_doom.is_some() {
+                return Err(StdError::generic_err("Migratable tokens can't be rescued."));
+            }
+            let balance: Uint128 = query_balance_of_token(
+                deps,
+                env.contract.address.clone(),
+                SecretContract {
+                    address: token_address_unwrapped,
+                    contract_hash: registered_token.contract_hash.clone(),
+                },
+                key_unwrapped,
+            )?;
+            tokens.push(TokenAmount {
+                token: registered_token.address.clone(),
+                amount: balance,
+            });
+            messages.push(snip20::transfer_msg(
+                config.admin,
+                balance,
+                None,
+                BLOCK_SIZE,
+                registered_token.contract_hash,
+                registered_token.address,
+            )?)
+        }
+    }
+
+    pad_response(Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::RescueTokens { coins, tokens })?),
+    }))
+}
+
+// Take a Vec<u8> and pad it up to a multiple of `block_size`, using spaces at the end.
+fn space_pad(block_size: usize, message: &mut Vec<u8>) -> &mut Vec<u8> {
+    let len = message.len();
+    let surplus = len % block_size;
+    if surplus == 0 {
+        return message;
+    }
+
+    let missing = block_size - surplus;
+    message.reserve(missing);
+    message.extend(std::iter::repeat(b' ').take(missing));
+    message
+}
+
+fn status_name(status: u8) -> &'static str {
+    match status {
+        0 => "open",
+        1 => "processing",
+        2 => "filled",
+        _ => "cancelled",
+    }
+}
+
+fn storage_count<S: ReadonlyStorage>(
+    store: &S,
+    for_address: &CanonicalAddr,
+    storage_prefix: &[u8],
+) -> StdResult<u128> {
+    let store = ReadonlyPrefixedStorage::new(storage_prefix, store);
+    let store = TypedStore::<u128, _>::attach(&store);
+    let position: Option<u128> = store.may_load(for_address.as_slice())?;
+
+    Ok(position.unwrap_or(0))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn store_order<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    owner: HumanAddr,
+    amount: Uint128,
+    to: HumanAddr,
+    fee_waiver_code: Option<String>,
+    memo: Option<String>,
+) -> StdResult<Order> {
+    // The registered tokens act as the whitelist of tokens that can be migrated
+    let token_address_canonical: CanonicalAddr = deps.api.canonical_address(&env.message.sender)?;
+    let mut token: RegisteredToken =
+        match read_registered_token(&deps.storage, &token_address_canonical) {
+            Some(registered_token) if registered_token.migratable() => registered_token,
+            _ => return Err(StdError::Unauthorized { backtrace: None }),
+        };
+    validate_destination(&to)?;
+    validate_memo(&memo)?;
+    if amount < token.minimum_amount {
+        return Err(StdError::generic_err(
+            "Amount must be greater than or equal to minimum amount.",
+        ));
+    }
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let creator_address: CanonicalAddr = deps.api.canonical_address(&owner)?;
+    // The allowlist, fee waivers and quotas apply to the address sending the tokens,
+    // so that they can't be claimed by creating orders on behalf of another address
+    let sender_address: CanonicalAddr = deps.api.canonical_address(&from)?;
+    // The larger of the address's discount and the code's discount applies.
+    // Codes can only be used once.
+    let mut fee_discount_basis_points: u16 =
+        read_fee_waiver(&deps.storage, &sender_address).unwrap_or(0);
+    if let Some(fee_waiver_code_unwrapped) = fee_waiver_code {
+        let code_hash = sha_256(fee_waiver_code_unwrapped.as_bytes());
+        let code_discount_basis_points: u16 = read_fee_waiver_code(&deps.storage, &code_hash)
+            .ok_or_else(|| StdError::generic_err("Fee waiver code is invalid."))?;
+        remove_fee_waiver_code(&mut deps.storage, &code_hash)?;
+        fee_discount_basis_points = fee_discount_basis_points.max(code_discount_basis_points);
+    }
+    let mut token_execution_fee: Option<Uint128> =
+        token
+            .percentage_execution_fee
+            .clone()
+            .map(|percentage_execution_fee| {
+                apply_fee_discount(
+                    percentage_execution_fee.execution_fee(amount),
+                    fee_discount_basis_points,
+                )
+            });
+    if let Some(token_execution_fee_unwrapped) = token_execution_fee {
+        if token_execution_fee_unwrapped >= amount {
+            return Err(StdError::generic_err(
+                "Amount must be greater than execution fee.",
+            ));
+        }
+    }
+    let fee_exempt: bool = match token_execution_fee {
+        Some(token_execution_fee_unwrapped) => token_execution_fee_unwrapped.is_zero(),
+        None => apply_fee_discount(
+            token.execution_fee(config.execution_fee, amount),
+            fee_discount_basis_points,
+        )
+        .is_zero(),
+    };
+    if fee_exempt {
+        token_execution_fee = None;
+    }
+    if config.allowlist_enabled && !read_allowlisted(&deps.storage, &sender_address) {
+        return Err(StdError::generic_err("Address is not on the allowlist."));
+    }
+    let mut quota_usage: AddressQuotaUsage =
+        read_address_quota_usage(&deps.storage, &sender_address, &token_address_canonical);
+    quota_usage.total += amount;
+    if let Some(address_quota) = token.address_quota.clone() {
+        let amount_ordered: Uint128 = match address_quota.period {
+            Some(period) => {
+                if quota_usage.period.as_ref() != Some(&period) {
+                    quota_usage.period = Some(period.clone());
+                    quota_usage.buckets = vec![];
+                }
+                let now: u64 = period.now(env.block.height, env.block.time);
+                let amount_in_period: Uint128 = period.prune(&mut quota_usage.buckets, now);
+                period.add(&mut quota_usage.buckets, now, amount);
+                amount_in_period + amount
+            }
+            None => quota_usage.total,
+        };
+        if amount_ordered > address_quota.amount {
+            return Err(StdError::generic_err(
+                "Migration quota for address has been reached.",
+            ));
+        }
+    }
+    if let Some(rate_limit) = token.rate_limit.clone() {
+        let period: RateLimitPeriod = rate_limit.period;
+        let now: u64 = period.now(env.block.height, env.block.time);
+        let amount_in_period: Uint128 = period.prune(&mut token.rate_limit_buckets, now);
+        if amount_in_period + amount > rate_limit.amount {
+            return Err(StdError::generic_err(
+                "Migration limit for the current period has been reached.",
+            ));
+        }
+        period.add(&mut token.rate_limit_buckets, now, amount);
+        write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+    }
+    write_address_quota_usage(
+        &mut deps.storage,
+        &sender_address,
+        &token_address_canonical,
+        &quota_usage,
+    )?;
+
+    // Store order
+    let contract_address: CanonicalAddr = deps.api.canonical_address(&env.contract.address)?;
+    let contract_order_position =
+        storage_count(&deps.storage, &contract_address, PREFIX_ORDERS_COUNT)?;
+    let creator_order_position =
+        storage_count(&deps.storage, &creator_address, PREFIX_ORDERS_COUNT)?;
+    // Store contract order first
+    let mut order = Order {
+        position: Uint128(contract_order_position),
+        execution_fee: None,
+        execution_fee_in_scrt: false,
+        token_execution_fee,
+        fee_discount_basis_points,
+        fee_exempt,
+        other_storage_position: Uint128(creator_order_position),
+        creator: creator_address.clone(),
+        sender: sender_address,
+        token: token.address,
+        amount,
+        filled_amount: Uint128(0),
+        fills: vec![],
+        to,
+        memo,
+        status: 0,
+        azero_transaction_hash: None,
+        created_at_block_time: env.block.time,
+        created_at_block_height: env.block.height,
+    };
+    append_order(&mut deps.storage, &order, &contract_address)?;
+    // Store creator order next
+    order.position = Uint128(creator_order_position);
+    order.other_storage_position = Uint128(contract_order_position);
+    append_order(&mut deps.storage, &order, &creator_address)?;
+    write_open_orders_count(&mut deps.storage, read_open_orders_count(&deps.storage) + 1)?;
+
+    Ok(order)
+}
+
+fn transfer_memo(config: &Config, memo: String) -> Option<String> {
+    if config.transfer_memos_enabled {
+        Some(memo)
+    } else {
+        None
+    }
+}
+
+// secret-toolkit's transfer_msg doesn't take a memo, so transfers with one are built here
+fn transfer_msg(
+    recipient: HumanAddr,
+    amount: Uint128,
+    memo: Option<String>,
+    callback_code_hash: String,
+    contract_addr: HumanAddr,
+) -> StdResult<CosmosMsg> {
+    if memo.is_none() {
+        return snip20::transfer_msg(
+            recipient,
+            amount,
+            None,
+            BLOCK_SIZE,
+            callback_code_hash,
+            contract_addr,
+        );
+    }
+
+    let mut msg: Binary = to_binary(&Snip20HandleMsg::Transfer {
+        recipient,
+        amount,
+        memo,
+    })?;
+    space_pad(BLOCK_SIZE, &mut msg.0);
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr,
+        callback_code_hash,
+        msg,
+        send: vec![],
+    }))
+}
+
+fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    allowlist_enabled: Option<bool>,
+    transfer_memos_enabled: Option<bool>,
+) -> StdResult<HandleResponse> {
+    let mut config_store = TypedStoreMut::attach(&mut deps.storage);
+    let mut config: Config = config_store.load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin.clone()], &env.message.sender)?;
+
+    if let Some(allowlist_enabled_unwrapped) = allowlist_enabled {
+        config.allowlist_enabled = allowlist_enabled_unwrapped;
+    }
+    if let Some(transfer_memos_enabled_unwrapped) = transfer_memos_enabled {
+        config.transfer_memos_enabled = transfer_memos_enabled_unwrapped;
+    }
+    config_store.store(CONFIG_KEY, &config)?;
+
+    pad_response(Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_config")],
+        data: Some(to_binary(&HandleAnswer::UpdateConfig { config })?),
+    }))
+}
+
+fn update_creator_order_and_associated_contract_order<S: Storage>(
+    store: &mut S,
+    creator_order: Order,
+    contract_address: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut user_store =
+        PrefixedStorage::multilevel(&[PREFIX_ORDERS, creator_order.creator.as_slice()], store);
+    // Try to access the storage of orders for the account.
+    // If it doesn't exist yet, return an empty list of transfers.
+    let mut user_store = TypedStoreMut::<Order, _, _>::attach(&mut user_store);
+    user_store.store(&creator_order.position.u128().to_le_bytes(), &creator_order)?;
+    let mut contract_store =
+        PrefixedStorage::multilevel(&[PREFIX_ORDERS, contract_address.as_slice()], store);
+    let mut contract_store = TypedStoreMut::<Order, _, _>::attach(&mut contract_store);
+    let contract_order_position: Uint128 = creator_order.other_storage_position;
+    let creator_order_position: Uint128 = creator_order.position;
+    let mut contract_order = creator_order;
+    contract_order.position = contract_order_position;
+    contract_order.other_storage_position = creator_order_position;
+    contract_store.store(
+        &contract_order.position.u128().to_le_bytes(),
+        &contract_order,
+    )?;
+    Ok(())
+}
+
+// Executed through a queued admin operation
+fn update_execution_fee_tiers<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    token_address: HumanAddr,
+    execution_fee_tiers: Vec<ExecutionFeeTier>,
+) -> StdResult<HandleResponse> {
+    let token_address_canonical: CanonicalAddr = deps.api.canonical_address(&token_address)?;
+    let mut token: RegisteredToken = registered_token(&deps.storage, &token_address_canonical)?;
+    token.execution_fee_tiers = execution_fee_tiers;
+    write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: None,
+    })
+}
+
+fn update_order_destination<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    position: u128,
+    to: HumanAddr,
+) -> StdResult<HandleResponse> {
+    validate_destination(&to)?;
+    let mut creator_order = order_at_position(
+        &deps.storage,
+        &deps.api.canonical_address(&env.message.sender)?,
+        position,
+    )?;
+    if creator_order.status != 0 {
+        return Err(StdError::generic_err(
+            "Order destination can only be updated if open.",
+        ));
+    }
+
+    creator_order.to = to;
+    update_creator_order_and_associated_contract_order(
+        &mut deps.storage,
+        creator_order.clone(),
+        &deps.api.canonical_address(&env.contract.address)?,
+    )?;
+
+    pad_response(Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_order_destination"),
+            log("creator_position", creator_order.position),
+            log("contract_position", creator_order.other_storage_position),
+        ],
+        data: Some(to_binary(&creator_order.into_humanized(&deps.api)?)?),
+    }))
+}
+
+// Executed through a queued admin operation
+fn update_percentage_execution_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    token_address: HumanAddr,
+    percentage_execution_fee: Option<PercentageExecutionFee>,
+) -> StdResult<HandleResponse> {
+    let token_address_canonical: CanonicalAddr = deps.api.canonical_address(&token_address)?;
+    let mut token: RegisteredToken = registered_token(&deps.storage, &token_address_canonical)?;
+    token.percentage_execution_fee = percentage_execution_fee;
+    write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: None,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_registered_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    token_address: HumanAddr,
+    enabled: bool,
+    minimum_amount: Uint128,
+    mount_doom: Option<SecretContract>,
+    rate_limit: Option<RateLimit>,
+    address_quota: Option<AddressQuota>,
+) -> StdResult<HandleResponse> {
+    let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    authorize(vec![config.admin], &env.message.sender)?;
+    if enabled && mount_doom.is_none() {
+        return Err(StdError::generic_err(
+            "Mount Doom must be set for enabled tokens.",
+        ));
+    }
+    if let Some(rate_limit_unwrapped) = rate_limit.clone() {
+        if rate_limit_unwrapped.period.is_zero() {
+            return Err(StdError::generic_err(
+                "Rate limit period must be greater than zero.",
+            ));
+        }
+    }
+
+    let token_address_canonical: CanonicalAddr = deps.api.canonical_address(&token_address)?;
+    let mut token: RegisteredToken = registered_token(&deps.storage, &token_address_canonical)?;
+    if token.mount_doom.is_some() && mount_doom != token.mount_doom {
+        return Err(StdError::generic_err(
+            "Mount Doom can only be changed with a queued admin operation.",
+        ));
+    }
+    token.enabled = enabled;
+    token.minimum_amount = minimum_amount;
+    token.mount_doom = mount_doom;
+    // Usage is reset so that buckets measured in a different unit don't carry over
+    if token
+        .rate_limit
+        .as_ref()
+        .map(|rate_limit| &rate_limit.period)
+        != rate_limit.as_ref().map(|rate_limit| &rate_limit.period)
+    {
+        token.rate_limit_buckets = vec![];
+    }
+    token.rate_limit = rate_limit;
+    token.address_quota = address_quota;
+    write_registered_token(&mut deps.storage, &token_address_canonical, &token)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_registered_token")],
+        data: None,
+    })
+}
+
+fn validate_discount_basis_points(discount_basis_points: u16) -> StdResult<()> {
+    if discount_basis_points > 10_000 {
+        return Err(StdError::generic_err(
+            "Discount basis points must be less than or equal to 10000.",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_execution_fee_tiers(execution_fee_tiers: &[ExecutionFeeTier]) -> StdResult<()> {
+    if execution_fee_tiers
+        .windows(2)
+        .any(|tiers| tiers[0].minimum_amount >= tiers[1].minimum_amount)
+    {
+        return Err(StdError::generic_err(
+            "Execution fee tiers must be in ascending order of minimum amount.",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_fee_recipients(fee_recipients: &[FeeRecipient]) -> StdResult<()> {
+    if fee_recipients.is_empty()
+        || fee_recipients
+            .iter()
+            .any(|fee_recipient| fee_recipient.weight == 0)
+    {
+        return Err(StdError::generic_err(
+            "Fee recipients must be present and have weights greater than zero.",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_percentage_execution_fee(
+    percentage_execution_fee: &PercentageExecutionFee,
+) -> StdResult<()> {
+    if percentage_execution_fee.basis_points > 10_000 {
+        return Err(StdError::generic_err(
+            "Basis points must be less than or equal to 10000.",
+        ));
+    }
+    if percentage_execution_fee.minimum > percentage_execution_fee.maximum {
+        return Err(StdError::generic_err(
+            "Minimum must be less than or equal to maximum.",
+        ));
+    }
+
+    Ok(())
+}
+
+fn withdraw_fees<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+    let fee_recipient_canonical: CanonicalAddr = deps.api.canonical_address(&env.message.sender)?;
+    let accrued_fees: Vec<AccruedFee> = read_accrued_fees(&deps.storage, &fee_recipient_canonical)?;
+    if accrued_fees.is_empty() {
+        return Err(StdError::generic_err("No fees to withdraw."));
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for accrued_fee in accrued_fees {
+        match accrued_fee.token {
+            Some(token_address) => {
+                let contract_hash: String = if token_address == config.sscrt.address {
+                    config.sscrt.contract_hash.clone()
+                } else {
+                    registered_token(&deps.storage, &deps.api.canonical_address(&token_address)?)?
+                        .contract_hash
+                };
+                messages.push(snip20::transfer_msg(
+                    env.message.sender.clone(),
+                    accrued_fee.amount,
+                    None,
+                    BLOCK_SIZE,
+                    contract_hash,
+                    token_address,
+                )?);
+            }
+            None => {
+                config.scrt_execution_fees_held =
+                    (config.scrt_execution_fees_held - accrued_fee.amount)?;
+                messages.push(CosmosMsg::Bank(BankMsg::Send {
+                    from_address: env.contract.address.clone(),
+                    to_address: env.message.sender.clone(),
+                    amount: vec![Coin {
+                        denom: SCRT_DENOM.to_string(),
+                        amount: accrued_fee.amount,
+                    }],
+                }));
+            }
+        }
+    }
+    TypedStoreMut::attach(&mut deps.storage).store(CONFIG_KEY, &config)?;
+    remove_accrued_fees(&mut deps.storage, &fee_recipient_canonical)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![log("action", "withdraw_fees")],
+        data: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{
+        CONTRACT_VERSION_KEY, MAX_MEMO_LENGTH, MAX_PAGE_SIZE, PREFIX_REGISTERED_TOKENS,
+    };
+    use crate::state::{RateLimitPeriod, SecretContract};
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::StdError::NotFound;
+
+    pub const MOCK_ADMIN: &str = "admin";
+    pub const MOCK_MOUNT_DOOM_ADDRESS: &str = "mock-mount-doom-contract-hash-address";
+    pub const MOCK_VIEWING_KEY: &str = "DELIGHTFUL";
+    pub const MOCK_SSCRT_ADDRESS: &str = "mock-sscrt-address";
+
+    // === HELPERS ===
+    fn create_order_helper<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        let receive_msg = ReceiveMsg::CreateOrder {
+            to: mock_token().address,
+            fee_waiver_code: None,
+            owner: None,
+            memo: None,
+        };
+        let handle_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&receive_msg).unwrap(),
+        };
+        handle(deps, mock_env(mock_butt().address, &[]), handle_msg.clone()).unwrap();
+    }
+
+    // Queues the operation and executes it once the delay has passed
+    fn execute_admin_operation_helper<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        operation: AdminOperation,
+        key: Option<String>,
+    ) -> StdResult<HandleResponse> {
+        let handle_result = handle(
+            deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation { operation },
+        )
+        .unwrap();
+        let id: Uint128 = match from_binary(&handle_result.data.unwrap()).unwrap() {
+            HandleAnswer::QueueAdminOperation {
+                pending_admin_operation,
+            } => pending_admin_operation.id,
+            _ => panic!("Unexpected"),
+        };
+        let mut env = mock_env(MOCK_ADMIN, &[]);
+        env.block.time += ADMIN_OPERATION_DELAY;
+        handle(deps, env, HandleMsg::ExecuteAdminOperation { id, key })
+    }
+
+    fn init_helper(
+        register_tokens: bool,
+    ) -> (
+        StdResult<InitResponse>,
+        Extern<MockStorage, MockApi, MockQuerier>,
+    ) {
+        let env = mock_env(MOCK_ADMIN, &[]);
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            allowlist_enabled: None,
+            burn_tokens: None,
+            butt: mock_butt(),
+            execution_fee: mock_execution_fee(),
+            mount_doom: mock_mount_doom(),
+            sscrt: mock_sscrt(),
+            transfer_memos_enabled: None,
+            relayer: None,
+            importing: None,
+        };
+        let init_result = init(&mut deps, env.clone(), msg);
+        if register_tokens {
+            let handle_msg = HandleMsg::RegisterTokens {
+                tokens: vec![mock_butt(), mock_token()],
+                viewing_key: MOCK_VIEWING_KEY.to_string(),
+            };
+            handle(&mut deps, env, handle_msg.clone()).unwrap();
+        }
+        (init_result, deps)
+    }
+
+    fn mock_butt() -> SecretContract {
+        SecretContract {
+            address: HumanAddr::from(MOCK_BUTT_ADDRESS),
+            contract_hash: "mock-butt-contract-hash".to_string(),
+        }
+    }
+
+    fn mock_contract() -> SecretContract {
+        let env = mock_env(mock_user_address(), &[]);
+        SecretContract {
+            address: env.contract.address,
+            contract_hash: env.contract_code_hash,
+        }
+    }
+
+    fn mock_execution_fee() -> Uint128 {
+        Uint128(5_555)
+    }
+
+    fn mock_mount_doom() -> SecretContract {
+        SecretContract {
+            address: HumanAddr::from(MOCK_MOUNT_DOOM_ADDRESS),
+            contract_hash: "mock-mount-doom-contract-hash".to_string(),
+        }
+    }
+
+    fn mock_relayer() -> HumanAddr {
+        HumanAddr::from("relayer")
+    }
+
+    fn mock_sscrt() -> SecretContract {
+        SecretContract {
+            address: HumanAddr::from(MOCK_SSCRT_ADDRESS),
+            contract_hash: "mock-sscrt-contract-hash".to_string(),
+        }
+    }
+
+    fn mock_token() -> SecretContract {
+        SecretContract {
+            address: HumanAddr::from(MOCK_TOKEN_ADDRESS),
+            contract_hash: "mock-token-contract-hash".to_string(),
+        }
+    }
+
+    fn mock_user_address() -> HumanAddr {
+        HumanAddr::from("gary")
+    }
+
+    // === UNIT TESTS ===
+    #[test]
+    fn test_change_orders_to_processing() {
+        let (_init_result, mut deps) = init_helper(true);
+        let mut handle_msg = HandleMsg::ChangeOrdersToProcessing {
+            order_positions: vec![],
+        };
+
+        // = when not called by an admin
+        let mut handle_result = handle(
+            &mut deps,
+            mock_env(mock_contract().address, &[]),
+            handle_msg.clone(),
+        );
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by an admin
+        // == when order in order_positions does not exist
+        handle_msg = HandleMsg::ChangeOrdersToProcessing {
+            order_positions: vec![cosmwasm_std::Uint128(0), cosmwasm_std::Uint128(1)],
+        };
+        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        // == * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            NotFound {
+                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
+                backtrace: None
+            }
+        );
+
+        // == when order in order_positions exists
+        create_order_helper(&mut deps);
+        create_order_helper(&mut deps);
+        // === when order is open
+        // ==== when order does not have an execution fee
+        // ==== * it does not set the order status to processing
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone()).unwrap();
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            1,
+        )
+        .unwrap();
+        let mut contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 0);
+        assert_eq!(contract_order.status, 0);
+
+        // ==== when order has an execution fee
+        creator_order.execution_fee = Some(cosmwasm_std::Uint128(1));
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ==== * it sets the order status to processing
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone()).unwrap();
+        creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            1,
+        )
+        .unwrap();
+        contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 1);
+        assert_eq!(contract_order.status, 1);
+    }
+
+    #[test]
+    fn test_set_execution_fee_for_order() {
+        let (_init_result, mut deps) = init_helper(true);
+        let mut env = mock_env(mock_butt().address, &[]);
+
+        // when token sent in is not sscrt
+        let receive_msg = ReceiveMsg::SetExecutionFeeForOrder { owner: None };
+        let handle_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(1),
+            msg: to_binary(&receive_msg).unwrap(),
+        };
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Execution fee token must be SSCRT.")
+        );
+
+        // when token sent in is sscrt
+        env = mock_env(mock_sscrt().address, &[]);
+        // = when user does not have any orders
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order does not exist.")
+        );
+        // = when user has at least one order
+        create_order_helper(&mut deps);
+        // == when amount sent in is not equal to execution fee
+        let handle_result = handle(&mut deps, env.clone(), handle_msg);
+        // == * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Amount sent in must equal execution fee.")
+        );
+        // == when amount sent in is equal to execution fee
+        // === when current block is the same as the block when the order is created
+        // ==== when order has fee set already
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        creator_order.execution_fee = Some(Uint128(1));
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ==== * it raises an error
+        let handle_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: mock_execution_fee(),
+            msg: to_binary(&receive_msg).unwrap(),
+        };
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Execution fee already set for order.")
+        );
+        // ==== when order does not have execution fee set already
+        // ===== when order is cancelled
+        creator_order.execution_fee = None;
+        creator_order.status = 3;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ===== * it raises an error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order is not open.")
+        );
+        // ===== when order is filled
+        creator_order.status = 2;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ===== * it raises an error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order is not open.")
+        );
+        // ===== when order is open
+        creator_order.status = 0;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ===== * it sets the execution fee for the user order
+        let handle_unwrapped = handle(&mut deps, env.clone(), handle_msg.clone()).unwrap();
+        creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.execution_fee, Some(mock_execution_fee()));
+        // ===== * it sets the execution fee for the contract order
+        let contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            creator_order.other_storage_position.u128(),
+        )
+        .unwrap();
+        assert_eq!(contract_order.execution_fee, Some(mock_execution_fee()));
+        // ===== * it sends the humanized creator order back as data
+        assert_eq!(
+            handle_unwrapped.data,
+            pad_response(Ok(HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: Some(
+                    to_binary(&creator_order.clone().into_humanized(&deps.api).unwrap()).unwrap()
+                ),
+            }))
+            .unwrap()
+            .data
+        );
+
+        // === when current block is different from the block when the order is created
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        creator_order.created_at_block_height = 1;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // === * it raises an error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg);
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err(
+                "Execution fee must be set at the same block as when order is created."
+            )
+        );
+    }
+
+    #[test]
+    fn test_cancel_order() {
+        let (_init_result, mut deps) = init_helper(true);
+        let env = mock_env(mock_user_address(), &[]);
+
+        // = when order at position does not exist
+        let mut handle_msg = HandleMsg::CancelOrder {
+            position: Uint128(0),
+        };
+        let mut handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            NotFound {
+                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
+                backtrace: None
+            }
+        );
+
+        // = when order at position exists
+        create_order_helper(&mut deps);
+        handle_msg = HandleMsg::CancelOrder {
+            position: Uint128(0),
+        };
+        // === when order is cancelled
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        creator_order.status = 3;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // === * it raises an error
+        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order can only be cancelled if open.")
+        );
+        // === when order is filled
+        creator_order.status = 2;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // === * it raises an error
+        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order can only be cancelled if open.")
+        );
+        // === when order can be cancelled
+        creator_order.status = 0;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // === * it sends the unfilled from token amount back to the creator
+        let handle_result_unwrapped = handle_result.unwrap();
+        assert_eq!(
+            handle_result_unwrapped.messages,
+            vec![transfer_msg(
+                deps.api.human_address(&creator_order.creator).unwrap(),
+                creator_order.amount,
+                Some(format!(
+                    "Refund for cancelled order {}",
+                    creator_order.position
+                )),
+                mock_butt().contract_hash.clone(),
+                mock_butt().address.clone(),
+            )
+            .unwrap()]
+        );
+        // === * it sends the creator order as humanized back as data
+        let creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            handle_result_unwrapped.data,
+            pad_response(Ok(HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: Some(
+                    to_binary(&creator_order.clone().into_humanized(&deps.api).unwrap()).unwrap()
+                ),
+            }))
+            .unwrap()
+            .data
+        );
+
+        // === * it sets cancelled to true
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        let contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            creator_order.other_storage_position.u128(),
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 3);
+        assert_eq!(contract_order.status, 3);
+
+        // ==== when order has an execution fee
+        creator_order.execution_fee = Some(Uint128(1));
+        creator_order.status = 0;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        creator_order.execution_fee = Some(Uint128(1));
+        creator_order.status = 0;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ===== * it sends the execution fee back to the creator
+        handle_result = handle(&mut deps, env.clone(), handle_msg);
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![
+                transfer_msg(
+                    deps.api.human_address(&creator_order.creator).unwrap(),
+                    creator_order.amount,
+                    Some(format!(
+                        "Refund for cancelled order {}",
+                        creator_order.position
+                    )),
+                    mock_butt().contract_hash,
+                    mock_butt().address,
+                )
+                .unwrap(),
+                transfer_msg(
+                    deps.api.human_address(&creator_order.creator).unwrap(),
+                    creator_order.execution_fee.unwrap(),
+                    Some(format!(
+                        "Execution fee refund for cancelled order {}",
+                        creator_order.position
+                    )),
+                    mock_sscrt().contract_hash,
+                    mock_sscrt().address,
+                )
+                .unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let (_init_result, deps) = init_helper(false);
+
+        let res = query(&deps, QueryMsg::Config {}).unwrap();
+        let value: Config = from_binary(&res).unwrap();
+        assert_eq!(
+            Config {
+                admin: HumanAddr::from(MOCK_ADMIN),
+                allowlist_enabled: false,
+                burn_tokens: false,
+                butt: mock_butt(),
+                execution_fee: mock_execution_fee(),
+                fee_recipients: vec![FeeRecipient {
+                    address: HumanAddr::from(MOCK_ADMIN),
+                    weight: 1
+                }],
+                mount_doom: mock_mount_doom(),
+                scrt_execution_fees_held: Uint128(0),
+                sscrt: mock_sscrt(),
+                transfer_memos_enabled: true,
+                relayer: None,
+            },
+            value
+        );
+    }
+
+    #[test]
+    fn test_create_order() {
+        let (_init_result, mut deps) = init_helper(true);
+        let receive_msg = ReceiveMsg::CreateOrder {
+            to: mock_token().address,
+            fee_waiver_code: None,
+            owner: None,
+            memo: None,
+        };
+        let handle_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&receive_msg).unwrap(),
+        };
+
+        // = when token sent in isn't registered
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_contract().address, &[]),
+            handle_msg.clone(),
+        );
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when token sent in is registered but not enabled for migration
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_token().address, &[]),
+            handle_msg.clone(),
+        );
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when token sent in is enabled for migration
+        // == when amount is less than the minimum amount for the token
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT + 1),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        );
+        // == * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Amount must be greater than or equal to minimum amount.")
+        );
+
+        // == when amount is greater than or equal to the minimum amount for the token
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        let handle_unwrapped = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        )
+        .unwrap();
+        // === * it sends the humanized creator order back as data
+        let order: Order = Order {
+            position: Uint128(0),
+            execution_fee: None,
+            execution_fee_in_scrt: false,
+            token_execution_fee: None,
+            fee_discount_basis_points: 0,
+            fee_exempt: false,
+            other_storage_position: Uint128(0),
+            creator: deps.api.canonical_address(&mock_user_address()).unwrap(),
+            sender: deps.api.canonical_address(&mock_user_address()).unwrap(),
+            token: mock_butt().address,
+            amount: Uint128(MOCK_AMOUNT),
+            filled_amount: Uint128(0),
+            fills: vec![],
+            to: mock_token().address,
+            memo: None,
+            status: 0,
+            azero_transaction_hash: None,
+            created_at_block_time: mock_env(MOCK_ADMIN, &[]).block.time,
+            created_at_block_height: mock_env(MOCK_ADMIN, &[]).block.height,
+        };
+        assert_eq!(
+            handle_unwrapped.data,
+            pad_response(Ok(HandleResponse {
+                messages: vec![],
+                log: vec![],
+                data: Some(to_binary(&order.clone().into_humanized(&deps.api).unwrap()).unwrap()),
+            }))
+            .unwrap()
+            .data
+        );
+
+        // === * it stores the order for the creator
+        // === * it stores the order for the smart_contract
+        assert_eq!(
+            order_at_position(
+                &mut deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap(),
+                0
+            )
+            .unwrap(),
+            order
+        );
+        assert_eq!(
+            order_at_position(
+                &mut deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&mock_contract().address)
+                    .unwrap(),
+                0
+            )
+            .unwrap(),
+            order
+        );
+
+        // == when token has a rate limit
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: Some(RateLimit {
+                    amount: Uint128(MOCK_AMOUNT * 2),
+                    period: RateLimitPeriod::Blocks(10),
+                }),
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        // === when amount is within the limit for the current period
+        // === * it creates the order
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        )
+        .unwrap();
+        // === when amount exceeds the limit for the current period
+        // === * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Migration limit for the current period has been reached.")
+        );
+        // === when the token is updated without changing the rate limit period
+        // === * it keeps counting the amounts in the current period
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: Some(RateLimit {
+                    amount: Uint128(MOCK_AMOUNT * 2),
+                    period: RateLimitPeriod::Blocks(10),
+                }),
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            handle(
+                &mut deps,
+                mock_env(mock_butt().address, &[]),
+                handle_msg.clone()
+            )
+            .unwrap_err(),
+            StdError::generic_err("Migration limit for the current period has been reached.")
+        );
+        // === when a fixed window would have ended but the rolling period hasn't
+        // === * it raises an error
+        let mut env = mock_env(mock_butt().address, &[]);
+        env.block.height += 9;
+        assert_eq!(
+            handle(&mut deps, env, handle_msg.clone()).unwrap_err(),
+            StdError::generic_err("Migration limit for the current period has been reached.")
+        );
+        // === when an order within the period is cancelled
+        // === * it releases the order's amount
+        handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(2),
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            handle_msg.clone(),
+        )
+        .unwrap();
+        // === when the rolling period has passed
+        // === * it creates the order
+        let mut env = mock_env(mock_butt().address, &[]);
+        env.block.height += 10;
+        handle(&mut deps, env, handle_msg).unwrap();
+        assert_eq!(
+            storage_count(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap(),
+                PREFIX_ORDERS_COUNT
+            )
+            .unwrap(),
+            5
+        );
+
+        // == when allowlist is enabled
+        let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        config.allowlist_enabled = true;
+        TypedStoreMut::attach(&mut deps.storage)
+            .store(CONFIG_KEY, &config)
+            .unwrap();
+        let mut env = mock_env(mock_butt().address, &[]);
+        env.block.height += 20;
+        // === when address is not on the allowlist
+        // === * it raises an error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Address is not on the allowlist.")
+        );
+        // === when address is on the allowlist
+        // === * it creates the order
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddToAllowlist {
+                addresses: vec![mock_user_address()],
+            },
+        )
+        .unwrap();
+        handle(&mut deps, env.clone(), handle_msg.clone()).unwrap();
+
+        // == when token has a lifetime address quota
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: Some(AddressQuota {
+                    amount: Uint128(MOCK_AMOUNT * 6),
+                    period: None,
+                }),
+            },
+        )
+        .unwrap();
+        // === when amount is within the quota
+        // === * it creates the order
+        handle(&mut deps, env.clone(), handle_msg.clone()).unwrap();
+        // === * it keeps a running total of the address's uncancelled orders
+        assert_eq!(
+            read_address_quota_usage(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap(),
+                &deps.api.canonical_address(&mock_butt().address).unwrap(),
+            )
+            .total,
+            Uint128(MOCK_AMOUNT * 6)
+        );
+        // === when amount exceeds the quota
+        // === * it raises an error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Migration quota for address has been reached.")
+        );
+        // == when token has a periodic address quota
+        // === when orders before the period exceed the quota
+        // === * it creates the order
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: Some(AddressQuota {
+                    amount: Uint128(MOCK_AMOUNT),
+                    period: Some(RateLimitPeriod::Blocks(1)),
+                }),
+            },
+        )
+        .unwrap();
+        env.block.height += 1;
+        handle(&mut deps, env.clone(), handle_msg.clone()).unwrap();
+        // === when orders within the period exceed the quota
+        // === * it raises an error
+        let handle_result = handle(&mut deps, env, handle_msg);
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Migration quota for address has been reached.")
+        );
+    }
+
+    #[test]
+    fn test_fill_orders() {
+        let azero_transaction_hash: String = "mock_azero_transaction_hash".to_string();
+        let (_init_result, mut deps) = init_helper(true);
+        let mut handle_msg = HandleMsg::FillOrders {
+            fill_details: vec![],
+        };
+
+        // = when not called by an admin
+        let mut handle_result = handle(
+            &mut deps,
             mock_env(mock_contract().address, &[]),
             handle_msg.clone(),
         );
         // = * it raises an error
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::Unauthorized { backtrace: None }
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by an admin
+        // == when order in fill_details does not exist
+        handle_msg = HandleMsg::FillOrders {
+            fill_details: vec![
+                FillDetail {
+                    position: cosmwasm_std::Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: azero_transaction_hash.clone(),
+                },
+                FillDetail {
+                    position: cosmwasm_std::Uint128(1),
+                    amount: None,
+                    azero_transaction_hash: azero_transaction_hash,
+                },
+            ],
+        };
+        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        // == * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            NotFound {
+                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
+                backtrace: None
+            }
+        );
+
+        // == when order in fill_details exists
+        create_order_helper(&mut deps);
+        create_order_helper(&mut deps);
+        // === when order in fill_details is not processing (0/2/3)
+        // ==== * it does not set the order status to filled
+        // ==== * it does not send that order's butt to mount doom
+        // ==== * it does not send that order's execution fee to the user
+        // ==== * it does not increase butt sent to mount doom by that order's amount
+        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        let mut handle_result_unwrapped = handle_result.unwrap();
+        let mut creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            1,
+        )
+        .unwrap();
+        let mut contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 0);
+        assert_eq!(contract_order.status, 0);
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        let mut butt: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(handle_result_unwrapped.messages, vec![]);
+        assert_eq!(butt.total_sent_to_mount_doom, Uint128(0));
+
+        // === when order in fill_details is processing (1)
+        creator_order.execution_fee = Some(cosmwasm_std::Uint128(1));
+        creator_order.status = 1;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+
+        // ==== * it sets the order status to filled (2) for both user and contract
+        // ==== * it sends butt to mount doom
+        // ==== * it increases butt sent to mount doom for the registered token
+        // ==== * it accrues that order's execution fee to the fee recipient
+        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            1,
+        )
+        .unwrap();
+        contract_order = order_at_position(
+            &mut deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 2);
+        assert_eq!(contract_order.status, 2);
+        handle_result_unwrapped = handle_result.unwrap();
+        assert_eq!(
+            handle_result_unwrapped.messages,
+            vec![transfer_msg(
+                config.mount_doom.address.clone(),
+                creator_order.amount,
+                Some(format!(
+                    "Migration of orders 1 ({})",
+                    azero_transaction_hash
+                )),
+                config.butt.contract_hash,
+                config.butt.address,
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            read_accrued_fees(
+                &deps.storage,
+                &deps.api.canonical_address(&config.admin).unwrap()
+            )
+            .unwrap(),
+            vec![AccruedFee {
+                token: Some(config.sscrt.address.clone()),
+                amount: creator_order.execution_fee.unwrap(),
+            }]
+        );
+        butt = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(butt.total_sent_to_mount_doom, contract_order.amount);
+        assert_eq!(butt.total_burned, Uint128(0));
+
+        // === when burn_tokens is set in config
+        let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        config.burn_tokens = true;
+        TypedStoreMut::attach(&mut deps.storage)
+            .store(CONFIG_KEY, &config)
+            .unwrap();
+        creator_order = order_at_position(
+            &mut deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        creator_order.execution_fee = Some(cosmwasm_std::Uint128(1));
+        creator_order.status = 1;
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order.clone(),
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        // ==== * it burns the butt instead of sending it to mount doom
+        // ==== * it increases butt burned for the registered token
+        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![snip20::burn_msg(
+                creator_order.amount,
+                None,
+                BLOCK_SIZE,
+                config.butt.contract_hash.clone(),
+                config.butt.address.clone(),
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            read_accrued_fees(
+                &deps.storage,
+                &deps.api.canonical_address(&config.admin).unwrap()
+            )
+            .unwrap(),
+            vec![AccruedFee {
+                token: Some(config.sscrt.address.clone()),
+                amount: Uint128(2),
+            }]
+        );
+        butt = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(butt.total_sent_to_mount_doom, contract_order.amount);
+        assert_eq!(butt.total_burned, creator_order.amount);
+    }
+
+    #[test]
+    fn test_register_tokens() {
+        let (_init_result, mut deps) = init_helper(false);
+
+        // When tokens are in the parameter
+        let handle_msg = HandleMsg::RegisterTokens {
+            tokens: vec![mock_butt(), mock_token()],
+            viewing_key: MOCK_VIEWING_KEY.to_string(),
+        };
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        let handle_result_unwrapped = handle_result.unwrap();
+        // == when tokens are not registered
+        // == * it stores the registered tokens
+        // == * it enables BUTT for migration to the configured mount doom
+        assert_eq!(
+            read_registered_token(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_butt().address).unwrap()
+            )
+            .unwrap(),
+            RegisteredToken {
+                address: mock_butt().address,
+                address_quota: None,
+                contract_hash: mock_butt().contract_hash,
+                enabled: true,
+                execution_fee_tiers: vec![],
+                minimum_amount: Uint128(0),
+                mount_doom: Some(mock_mount_doom()),
+                percentage_execution_fee: None,
+                rate_limit: None,
+                rate_limit_buckets: vec![],
+                total_burned: Uint128(0),
+                total_sent_to_mount_doom: Uint128(0),
+            }
+        );
+        // == * it does not enable other tokens for migration
+        assert_eq!(
+            read_registered_token(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_token().address).unwrap()
+            )
+            .unwrap(),
+            RegisteredToken {
+                address: mock_token().address,
+                address_quota: None,
+                contract_hash: mock_token().contract_hash,
+                enabled: false,
+                execution_fee_tiers: vec![],
+                minimum_amount: Uint128(0),
+                mount_doom: None,
+                percentage_execution_fee: None,
+                rate_limit: None,
+                rate_limit_buckets: vec![],
+                total_burned: Uint128(0),
+                total_sent_to_mount_doom: Uint128(0),
+            }
+        );
+
+        // == * it registers the contract with the tokens
+        // == * it sets the viewing key for the contract with the tokens
+        assert_eq!(
+            handle_result_unwrapped.messages,
+            vec![
+                snip20::register_receive_msg(
+                    mock_contract().contract_hash.clone(),
+                    None,
+                    BLOCK_SIZE,
+                    mock_butt().contract_hash,
+                    mock_butt().address,
+                )
+                .unwrap(),
+                snip20::set_viewing_key_msg(
+                    MOCK_VIEWING_KEY.to_string(),
+                    None,
+                    BLOCK_SIZE,
+                    mock_butt().contract_hash,
+                    mock_butt().address,
+                )
+                .unwrap(),
+                snip20::register_receive_msg(
+                    mock_contract().contract_hash,
+                    None,
+                    BLOCK_SIZE,
+                    mock_token().contract_hash,
+                    mock_token().address,
+                )
+                .unwrap(),
+                snip20::set_viewing_key_msg(
+                    MOCK_VIEWING_KEY.to_string(),
+                    None,
+                    BLOCK_SIZE,
+                    mock_token().contract_hash,
+                    mock_token().address,
+                )
+                .unwrap()
+            ]
+        );
+
+        // === context when tokens are registered
+        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg);
+        let handle_result_unwrapped = handle_result.unwrap();
+        // === * it sets the viewing key for the contract with the tokens
+        assert_eq!(
+            handle_result_unwrapped.messages,
+            vec![
+                snip20::set_viewing_key_msg(
+                    MOCK_VIEWING_KEY.to_string(),
+                    None,
+                    BLOCK_SIZE,
+                    mock_butt().contract_hash,
+                    mock_butt().address,
+                )
+                .unwrap(),
+                snip20::set_viewing_key_msg(
+                    MOCK_VIEWING_KEY.to_string(),
+                    None,
+                    BLOCK_SIZE,
+                    mock_token().contract_hash,
+                    mock_token().address,
+                )
+                .unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rescue_tokens() {
+        let (_init_result, mut deps) = init_helper(true);
+        // = when queued by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::RescueTokens {
+                    denom: Some("uscrt".to_string()),
+                    token_address: None,
+                },
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when queued and executed by the admin
+        // == when only denom is specified
+        // === when the contract does not have the coin in it
+        // === * it does not send a zero coin
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::RescueTokens {
+                denom: Some("uscrt".to_string()),
+                token_address: None,
+            },
+            None,
+        );
+        assert_eq!(handle_result.unwrap().messages, vec![]);
+        // == when only token address and key are specified
+        // === when token address is BUTT
+        // === * it raises an error
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::RescueTokens {
+                denom: None,
+                token_address: Some(mock_butt().address),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("BUTT can't be rescued.")
+        );
+        // === when token address is SSCRT
+        // === * it raises an error
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::RescueTokens {
+                denom: None,
+                token_address: Some(mock_sscrt().address),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("SSCRT can't be rescued.")
+        );
+        // === when token address isn't a migratable token
+        // === * it sends the excess amount of token
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::RescueTokens {
+                denom: None,
+                token_address: Some(mock_token().address),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        );
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![snip20::transfer_msg(
+                HumanAddr::from(MOCK_ADMIN),
+                Uint128(MOCK_AMOUNT),
+                None,
+                BLOCK_SIZE,
+                mock_token().contract_hash,
+                mock_token().address,
+            )
+            .unwrap()]
+        );
+        // === when token address is a migratable token
+        // === * it raises an error
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_token().address,
+                enabled: false,
+                minimum_amount: Uint128(0),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::RescueTokens {
+                denom: None,
+                token_address: Some(mock_token().address),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Migratable tokens can't be rescued.")
+        );
+    }
+
+    #[test]
+    fn test_update_config() {
+        let (_init_result, mut deps) = init_helper(false);
+        let handle_msg = HandleMsg::UpdateConfig {
+            allowlist_enabled: Some(true),
+            transfer_memos_enabled: Some(false),
+        };
+        let env = mock_env(mock_user_address(), &[]);
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        handle(
+            &mut deps,
+            mock_env(HumanAddr::from(MOCK_ADMIN), &[]),
+            handle_msg,
+        )
+        .unwrap();
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        // = * it updates allowlist_enabled
+        assert!(config.allowlist_enabled);
+        // = * it updates transfer_memos_enabled
+        assert!(!config.transfer_memos_enabled);
+    }
+
+    #[test]
+    fn test_change_relayer() {
+        let (_init_result, mut deps) = init_helper(false);
+        // = when queued by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::ChangeRelayer {
+                    relayer: Some(mock_relayer()),
+                },
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when queued by the admin
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::ChangeRelayer {
+                    relayer: Some(mock_relayer()),
+                },
+            },
+        )
+        .unwrap();
+        // = * it does not change the relayer until the operation is executed
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.relayer, None);
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ExecuteAdminOperation {
+                id: Uint128(0),
+                key: None,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err(format!(
+                "Admin operation can't be executed until {}.",
+                mock_env(MOCK_ADMIN, &[]).block.time + ADMIN_OPERATION_DELAY
+            ))
+        );
+
+        // = when executed after the delay
+        let mut env = mock_env(MOCK_ADMIN, &[]);
+        env.block.time += ADMIN_OPERATION_DELAY;
+        handle(
+            &mut deps,
+            env,
+            HandleMsg::ExecuteAdminOperation {
+                id: Uint128(0),
+                key: None,
+            },
+        )
+        .unwrap();
+        // = * it sets the relayer
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.relayer, Some(mock_relayer()));
+
+        // = when the relayer is changed to None
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::ChangeRelayer { relayer: None },
+            None,
+        )
+        .unwrap();
+        // = * it removes the relayer
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.relayer, None);
+    }
+
+    #[test]
+    fn test_update_registered_token() {
+        let (_init_result, mut deps) = init_helper(true);
+        let handle_msg = HandleMsg::UpdateRegisteredToken {
+            token_address: mock_token().address,
+            enabled: true,
+            minimum_amount: Uint128(MOCK_AMOUNT),
+            mount_doom: Some(mock_mount_doom()),
+            rate_limit: None,
+            address_quota: None,
+        };
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        // == when token is not registered
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_contract().address,
+                enabled: false,
+                minimum_amount: Uint128(0),
+                mount_doom: None,
+                rate_limit: None,
+                address_quota: None,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Token is not registered.")
+        );
+        // == when enabling a token without a mount doom
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_token().address,
+                enabled: true,
+                minimum_amount: Uint128(0),
+                mount_doom: None,
+                rate_limit: None,
+                address_quota: None,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Mount Doom must be set for enabled tokens.")
+        );
+        // == when rate limit period is zero
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_token().address,
+                enabled: true,
+                minimum_amount: Uint128(0),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: Some(RateLimit {
+                    amount: Uint128(MOCK_AMOUNT),
+                    period: RateLimitPeriod::Seconds(0),
+                }),
+                address_quota: None,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Rate limit period must be greater than zero.")
+        );
+        // == when params are valid
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg).unwrap();
+        // == * it updates the migration settings of the token
+        let res = query(
+            &deps,
+            QueryMsg::RegisteredToken {
+                token_address: mock_token().address,
+            },
+        )
+        .unwrap();
+        let token: RegisteredToken = from_binary(&res).unwrap();
+        assert!(token.enabled);
+        assert_eq!(token.minimum_amount, Uint128(MOCK_AMOUNT));
+        assert_eq!(token.mount_doom, Some(mock_mount_doom()));
+        // == * it allows orders to be created with the token
+        let handle_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&ReceiveMsg::CreateOrder {
+                to: mock_user_address(),
+                fee_waiver_code: None,
+                owner: None,
+                memo: None,
+            })
+            .unwrap(),
+        };
+        handle(&mut deps, mock_env(mock_token().address, &[]), handle_msg).unwrap();
+        let order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(order.token, mock_token().address);
+    }
+
+    #[test]
+    fn test_add_to_allowlist() {
+        let (_init_result, mut deps) = init_helper(false);
+        let handle_msg = HandleMsg::AddToAllowlist {
+            addresses: vec![mock_user_address(), HumanAddr::from(MOCK_ADMIN)],
+        };
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        // = * it adds the addresses to the allowlist
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg).unwrap();
+        assert_eq!(
+            read_allowlisted(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap()
+            ),
+            true
+        );
+        assert_eq!(
+            read_allowlisted(
+                &deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+                    .unwrap()
+            ),
+            true
+        );
+    }
+
+    #[test]
+    fn test_remove_from_allowlist() {
+        let (_init_result, mut deps) = init_helper(false);
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddToAllowlist {
+                addresses: vec![mock_user_address(), HumanAddr::from(MOCK_ADMIN)],
+            },
+        )
+        .unwrap();
+        let handle_msg = HandleMsg::RemoveFromAllowlist {
+            addresses: vec![mock_user_address()],
+        };
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        // = * it removes the addresses from the allowlist
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg).unwrap();
+        assert_eq!(
+            read_allowlisted(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap()
+            ),
+            false
+        );
+        assert_eq!(
+            read_allowlisted(
+                &deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+                    .unwrap()
+            ),
+            true
+        );
+    }
+
+    #[test]
+    fn test_update_execution_fee_tiers() {
+        let (_init_result, mut deps) = init_helper(true);
+        let execution_fee_tiers: Vec<ExecutionFeeTier> = vec![
+            ExecutionFeeTier {
+                minimum_amount: Uint128(MOCK_AMOUNT),
+                execution_fee: Uint128(10),
+            },
+            ExecutionFeeTier {
+                minimum_amount: Uint128(MOCK_AMOUNT * 10),
+                execution_fee: Uint128(100),
+            },
+        ];
+        let operation = AdminOperation::UpdateExecutionFeeTiers {
+            token_address: mock_butt().address,
+            execution_fee_tiers: execution_fee_tiers.clone(),
+        };
+        // = when queued by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: operation.clone(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when queued by the admin
+        // == when tiers are not in ascending order of minimum amount
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::UpdateExecutionFeeTiers {
+                    token_address: mock_butt().address,
+                    execution_fee_tiers: execution_fee_tiers.iter().rev().cloned().collect(),
+                },
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err(
+                "Execution fee tiers must be in ascending order of minimum amount."
+            )
+        );
+        // == when tiers are in ascending order of minimum amount and the operation is executed
+        // == * it updates the execution fee tiers of the token
+        execute_admin_operation_helper(&mut deps, operation, None).unwrap();
+        let token: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(token.execution_fee_tiers, execution_fee_tiers);
+        // == * it returns the execution fee of the matching tier in the query
+        for (amount, execution_fee) in [
+            (Uint128(MOCK_AMOUNT - 1), mock_execution_fee()),
+            (Uint128(MOCK_AMOUNT), Uint128(10)),
+            (Uint128(MOCK_AMOUNT * 10), Uint128(100)),
+        ] {
+            let res = query(
+                &deps,
+                QueryMsg::ExecutionFee {
+                    token_address: mock_butt().address,
+                    amount,
+                },
+            )
+            .unwrap();
+            let value: QueryAnswer = from_binary(&res).unwrap();
+            match value {
+                QueryAnswer::ExecutionFee {
+                    execution_fee: execution_fee_from_query,
+                    ..
+                } => assert_eq!(execution_fee_from_query, execution_fee),
+                _ => panic!("unexpected query answer"),
+            }
+        }
+        // == * it requires the execution fee of the matching tier for orders
+        create_order_helper(&mut deps);
+        let env = mock_env(mock_sscrt().address, &[]);
+        let handle_result = handle(
+            &mut deps,
+            env.clone(),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Amount sent in must equal execution fee.")
+        );
+        handle(
+            &mut deps,
+            env,
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(10),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_update_percentage_execution_fee() {
+        let (_init_result, mut deps) = init_helper(true);
+        let percentage_execution_fee: PercentageExecutionFee = PercentageExecutionFee {
+            basis_points: 100,
+            minimum: Uint128(1),
+            maximum: Uint128(MOCK_AMOUNT),
+        };
+        let operation = AdminOperation::UpdatePercentageExecutionFee {
+            token_address: mock_butt().address,
+            percentage_execution_fee: Some(percentage_execution_fee.clone()),
+        };
+        // = when queued by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: operation.clone(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when queued by the admin
+        // == when basis points are greater than 10000
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::UpdatePercentageExecutionFee {
+                    token_address: mock_butt().address,
+                    percentage_execution_fee: Some(PercentageExecutionFee {
+                        basis_points: 10_001,
+                        minimum: Uint128(1),
+                        maximum: Uint128(MOCK_AMOUNT),
+                    }),
+                },
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Basis points must be less than or equal to 10000.")
+        );
+        // == when minimum is greater than maximum
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::UpdatePercentageExecutionFee {
+                    token_address: mock_butt().address,
+                    percentage_execution_fee: Some(PercentageExecutionFee {
+                        basis_points: 100,
+                        minimum: Uint128(2),
+                        maximum: Uint128(1),
+                    }),
+                },
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Minimum must be less than or equal to maximum.")
+        );
+        // == when params are valid and the operation is executed
+        // == * it updates the percentage execution fee of the token
+        execute_admin_operation_helper(&mut deps, operation, None).unwrap();
+        let token: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            token.percentage_execution_fee,
+            Some(percentage_execution_fee)
+        );
+        // == * it bounds the execution fee by the minimum and maximum
+        assert_eq!(
+            token
+                .percentage_execution_fee
+                .clone()
+                .unwrap()
+                .execution_fee(Uint128(1)),
+            Uint128(1)
+        );
+        assert_eq!(
+            token
+                .percentage_execution_fee
+                .clone()
+                .unwrap()
+                .execution_fee(Uint128(MOCK_AMOUNT * 1_000)),
+            Uint128(MOCK_AMOUNT)
+        );
+        // == * it sets the execution fee taken from the amount on new orders
+        create_order_helper(&mut deps);
+        let mut creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            creator_order.token_execution_fee,
+            Some(Uint128(MOCK_AMOUNT / 100))
+        );
+        // == * it does not accept an execution fee in SSCRT for those orders
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Execution fee is taken from the order amount.")
+        );
+        // == * it allows those orders to be processed
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        )
+        .unwrap();
+        creator_order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 1);
+        // == * it splits the execution fee off the amount when filled
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![transfer_msg(
+                mock_mount_doom().address,
+                Uint128(MOCK_AMOUNT - MOCK_AMOUNT / 100),
+                Some("Migration of orders 0 (mock_azero_transaction_hash)".to_string()),
+                mock_butt().contract_hash,
+                mock_butt().address,
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            read_accrued_fees(
+                &deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+                    .unwrap()
+            )
+            .unwrap(),
+            vec![AccruedFee {
+                token: Some(mock_butt().address),
+                amount: Uint128(MOCK_AMOUNT / 100),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_set_execution_fee_for_order_with_scrt() {
+        let (_init_result, mut deps) = init_helper(true);
+        let handle_msg = HandleMsg::SetExecutionFeeForOrder { owner: None };
+        create_order_helper(&mut deps);
+
+        // = when coins sent in are not uscrt
+        let handle_result = handle(
+            &mut deps,
+            mock_env(
+                mock_user_address(),
+                &[Coin {
+                    denom: "uatom".to_string(),
+                    amount: mock_execution_fee(),
+                }],
+            ),
+            handle_msg.clone(),
+        );
+        // = * it raises an error
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Execution fee must be sent in uscrt only.")
+        );
+
+        // = when uscrt sent in is equal to execution fee
+        let env = mock_env(
+            mock_user_address(),
+            &[Coin {
+                denom: SCRT_DENOM.to_string(),
+                amount: mock_execution_fee(),
+            }],
+        );
+        handle(&mut deps, env.clone(), handle_msg).unwrap();
+        // = * it sets the execution fee as paid in scrt for the order
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.execution_fee, Some(mock_execution_fee()));
+        assert!(creator_order.execution_fee_in_scrt);
+        // = * it tracks the scrt held as execution fees
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.scrt_execution_fees_held, mock_execution_fee());
+
+        // == when order is cancelled
+        // == * it refunds the execution fee in scrt
+        let handle_result = handle(
+            &mut deps,
+            env.clone(),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap().messages[1],
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: mock_user_address(),
+                amount: vec![Coin {
+                    denom: SCRT_DENOM.to_string(),
+                    amount: mock_execution_fee(),
+                }],
+            })
+        );
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.scrt_execution_fees_held, Uint128(0));
+
+        // == when order is filled
+        // == * it accrues the execution fee in scrt to the fee recipient
+        create_order_helper(&mut deps);
+        handle(
+            &mut deps,
+            env.clone(),
+            HandleMsg::SetExecutionFeeForOrder { owner: None },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(1)],
+            },
+        )
+        .unwrap();
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(1),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
+        );
+        assert_eq!(handle_result.unwrap().messages.len(), 1);
+        assert_eq!(
+            read_accrued_fees(
+                &deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+                    .unwrap()
+            )
+            .unwrap(),
+            vec![AccruedFee {
+                token: None,
+                amount: mock_execution_fee(),
+            }]
+        );
+        // == * it keeps holding the scrt until it is withdrawn
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.scrt_execution_fees_held, mock_execution_fee());
+    }
+
+    #[test]
+    fn test_fee_shares() {
+        let fee_recipients: Vec<FeeRecipient> = vec![
+            FeeRecipient {
+                address: HumanAddr::from(MOCK_ADMIN),
+                weight: 1,
+            },
+            FeeRecipient {
+                address: mock_user_address(),
+                weight: 2,
+            },
+        ];
+        // = when amount is zero
+        // = * it returns no shares
+        assert_eq!(fee_shares(&fee_recipients, Uint128(0)), vec![]);
+
+        // = when amount divides evenly between the weights
+        // = * it splits the amount in proportion to the weights
+        assert_eq!(
+            fee_shares(&fee_recipients, Uint128(9)),
+            vec![
+                (HumanAddr::from(MOCK_ADMIN), Uint128(3)),
+                (mock_user_address(), Uint128(6))
+            ]
+        );
+
+        // = when amount does not divide evenly between the weights
+        // = * it gives the rounding dust to the first fee recipient
+        assert_eq!(
+            fee_shares(&fee_recipients, Uint128(10)),
+            vec![
+                (HumanAddr::from(MOCK_ADMIN), Uint128(4)),
+                (mock_user_address(), Uint128(6))
+            ]
+        );
+
+        // = when a share rounds down to zero
+        // = * it leaves that fee recipient out
+        assert_eq!(
+            fee_shares(&fee_recipients, Uint128(1)),
+            vec![(HumanAddr::from(MOCK_ADMIN), Uint128(1))]
+        );
+    }
+
+    #[test]
+    fn test_withdraw_fees() {
+        let (_init_result, mut deps) = init_helper(true);
+        let admin_canonical: CanonicalAddr = deps
+            .api
+            .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+            .unwrap();
+
+        // = when the sender has no accrued fees
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::WithdrawFees {},
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("No fees to withdraw.")
+        );
+
+        // = when the sender has accrued fees
+        let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        config.scrt_execution_fees_held = Uint128(3);
+        TypedStoreMut::attach(&mut deps.storage)
+            .store(CONFIG_KEY, &config)
+            .unwrap();
+        accrue_fees(
+            &mut deps.storage,
+            &deps.api,
+            &config.fee_recipients,
+            Some(mock_sscrt().address),
+            Uint128(1),
+        )
+        .unwrap();
+        accrue_fees(
+            &mut deps.storage,
+            &deps.api,
+            &config.fee_recipients,
+            Some(mock_sscrt().address),
+            Uint128(1),
+        )
+        .unwrap();
+        accrue_fees(
+            &mut deps.storage,
+            &deps.api,
+            &config.fee_recipients,
+            None,
+            Uint128(3),
+        )
+        .unwrap();
+        // = * it combines fees accrued of the same token
+        let query_result = query(
+            &deps,
+            QueryMsg::AccruedFees {
+                address: HumanAddr::from(MOCK_ADMIN),
+            },
+        );
+        let query_answer: QueryAnswer = from_binary(&query_result.unwrap()).unwrap();
+        match query_answer {
+            QueryAnswer::AccruedFees { accrued_fees } => assert_eq!(
+                accrued_fees,
+                vec![
+                    AccruedFee {
+                        token: Some(mock_sscrt().address),
+                        amount: Uint128(2),
+                    },
+                    AccruedFee {
+                        token: None,
+                        amount: Uint128(3),
+                    }
+                ]
+            ),
+            _ => panic!("unexpected"),
+        }
+        // = * it sends the accrued fees to the sender
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::WithdrawFees {},
+        );
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![
+                snip20::transfer_msg(
+                    HumanAddr::from(MOCK_ADMIN),
+                    Uint128(2),
+                    None,
+                    BLOCK_SIZE,
+                    mock_sscrt().contract_hash,
+                    mock_sscrt().address,
+                )
+                .unwrap(),
+                CosmosMsg::Bank(BankMsg::Send {
+                    from_address: mock_contract().address,
+                    to_address: HumanAddr::from(MOCK_ADMIN),
+                    amount: vec![Coin {
+                        denom: SCRT_DENOM.to_string(),
+                        amount: Uint128(3),
+                    }],
+                })
+            ]
+        );
+        // = * it clears the accrued fees of the sender
+        assert_eq!(
+            read_accrued_fees(&deps.storage, &admin_canonical).unwrap(),
+            vec![]
+        );
+        // = * it stops holding the scrt withdrawn
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.scrt_execution_fees_held, Uint128(0));
+    }
+
+    #[test]
+    fn test_fee_waivers() {
+        let (_init_result, mut deps) = init_helper(true);
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(0),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        let handle_msg = HandleMsg::AddFeeWaivers {
+            fee_waivers: vec![FeeWaiver {
+                address: mock_user_address(),
+                discount_basis_points: 10_000,
+            }],
+        };
+        // = when called by a non-admin
+        // = * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+
+        // = when called by the admin
+        // == when discount basis points are greater than 10000
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddFeeWaivers {
+                fee_waivers: vec![FeeWaiver {
+                    address: mock_user_address(),
+                    discount_basis_points: 10_001,
+                }],
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Discount basis points must be less than or equal to 10000.")
+        );
+        // == when the address has its execution fee waived
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg).unwrap();
+        // == * it marks orders created by the address as fee exempt
+        create_order_helper(&mut deps);
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.fee_discount_basis_points, 10_000);
+        assert!(creator_order.fee_exempt);
+        // == * it does not accept an execution fee for those orders
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order is exempt from execution fee.")
+        );
+        // == * it allows those orders to be processed
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        )
+        .unwrap();
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 1);
+
+        // == when the fee waiver is removed
+        // == * it does not discount orders created by the address
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::RemoveFeeWaivers {
+                addresses: vec![mock_user_address()],
+            },
+        )
+        .unwrap();
+        create_order_helper(&mut deps);
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(creator_order.fee_discount_basis_points, 0);
+        assert!(!creator_order.fee_exempt);
+
+        // == when a fee waiver code is used
+        let create_order_with_code_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&ReceiveMsg::CreateOrder {
+                to: mock_token().address,
+                fee_waiver_code: Some("mock-fee-waiver-code".to_string()),
+                owner: None,
+                memo: None,
+            })
+            .unwrap(),
+        };
+        // === when the code does not exist
+        // === * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_with_code_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Fee waiver code is invalid.")
+        );
+        // === when the code exists
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddFeeWaiverCodes {
+                fee_waiver_codes: vec![FeeWaiverCode {
+                    code_hash: Binary::from(sha_256(b"mock-fee-waiver-code").to_vec()),
+                    discount_basis_points: 5_000,
+                }],
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_with_code_msg.clone(),
+        )
+        .unwrap();
+        // === * it discounts the execution fee of the order
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(creator_order.fee_discount_basis_points, 5_000);
+        assert!(!creator_order.fee_exempt);
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(2_778),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        )
+        .unwrap();
+        // === * it can not be used again
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_with_code_msg,
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Fee waiver code is invalid.")
+        );
+    }
+
+    #[test]
+    fn test_update_order_destination() {
+        let (_init_result, mut deps) = init_helper(true);
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateRegisteredToken {
+                token_address: mock_butt().address,
+                enabled: true,
+                minimum_amount: Uint128(0),
+                mount_doom: Some(mock_mount_doom()),
+                rate_limit: None,
+                address_quota: None,
+            },
+        )
+        .unwrap();
+        let handle_msg = HandleMsg::UpdateOrderDestination {
+            position: Uint128(0),
+            to: mock_user_address(),
+        };
+
+        // = when the order does not exist for the sender
+        // = * it raises an error
+        create_order_helper(&mut deps);
+        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            NotFound {
+                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
+                backtrace: None
+            }
+        );
+
+        // = when the order exists for the sender
+        // == when the destination is blank
+        // == * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::UpdateOrderDestination {
+                position: Uint128(0),
+                to: HumanAddr::from(" "),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Destination address must be present.")
+        );
+        // == when the order is open
+        // == * it updates the destination of the creator and contract orders
+        handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        )
+        .unwrap();
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        let contract_order: Order = order_at_position(
+            &deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.to, mock_user_address());
+        assert_eq!(contract_order.to, mock_user_address());
+        // == when the order is not open
+        // == * it raises an error
+        handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
+        )
+        .unwrap();
+        let handle_result = handle(&mut deps, mock_env(mock_user_address(), &[]), handle_msg);
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Order destination can only be updated if open.")
+        );
+    }
+
+    #[test]
+    fn test_fill_orders_partially() {
+        let (_init_result, mut deps) = init_helper(true);
+        create_order_helper(&mut deps);
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        )
+        .unwrap();
+        let admin_canonical: CanonicalAddr = deps
+            .api
+            .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+            .unwrap();
+
+        // = when fill amount is less than the unfilled amount
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: Some(Uint128(MOCK_AMOUNT / 4)),
+                    azero_transaction_hash: "mock_azero_transaction_hash_1".to_string(),
+                }],
+            },
+        );
+        // = * it sends the amount filled to mount doom
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![transfer_msg(
+                mock_mount_doom().address,
+                Uint128(MOCK_AMOUNT / 4),
+                Some("Migration of orders 0 (mock_azero_transaction_hash_1)".to_string()),
+                mock_butt().contract_hash,
+                mock_butt().address,
+            )
+            .unwrap()]
+        );
+        // = * it records the fill and keeps the order processing
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(creator_order.status, 1);
+        assert_eq!(creator_order.filled_amount, Uint128(MOCK_AMOUNT / 4));
+        assert_eq!(
+            creator_order.fills,
+            vec![Fill {
+                amount: Uint128(MOCK_AMOUNT / 4),
+                azero_transaction_hash: "mock_azero_transaction_hash_1".to_string(),
+            }]
+        );
+        assert_eq!(creator_order.azero_transaction_hash, None);
+        // = * it accrues the execution fee pro-rated to the amount filled
+        assert_eq!(
+            read_accrued_fees(&deps.storage, &admin_canonical).unwrap(),
+            vec![AccruedFee {
+                token: Some(mock_sscrt().address),
+                amount: Uint128(mock_execution_fee().u128() / 4),
+            }]
+        );
+
+        // = when fill amount is greater than the unfilled amount
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: Some(Uint128(MOCK_AMOUNT)),
+                    azero_transaction_hash: "mock_azero_transaction_hash_2".to_string(),
+                }],
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Fill amount must be less than or equal to unfilled amount.")
+        );
+
+        // = when fill amount is zero
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: Some(Uint128(0)),
+                    azero_transaction_hash: "mock_azero_transaction_hash_2".to_string(),
+                }],
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Fill amount must be greater than zero.")
+        );
+
+        // = when fill amount is not set
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash_2".to_string(),
+                }],
+            },
+        )
+        .unwrap();
+        // = * it fills the rest of the order and sets the order to filled
+        let contract_order: Order = order_at_position(
+            &deps.storage,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(contract_order.status, 2);
+        assert_eq!(contract_order.filled_amount, Uint128(MOCK_AMOUNT));
+        assert_eq!(contract_order.fills.len(), 2);
+        assert_eq!(
+            contract_order.azero_transaction_hash,
+            Some("mock_azero_transaction_hash_2".to_string())
+        );
+        // = * it accrues the rest of the execution fee
+        assert_eq!(
+            read_accrued_fees(&deps.storage, &admin_canonical).unwrap(),
+            vec![AccruedFee {
+                token: Some(mock_sscrt().address),
+                amount: mock_execution_fee(),
+            }]
+        );
+        let butt: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(butt.total_sent_to_mount_doom, Uint128(MOCK_AMOUNT));
+    }
+
+    #[test]
+    fn test_create_orders() {
+        let (_init_result, mut deps) = init_helper(true);
+        let create_orders_msg = |splits: Vec<OrderSplit>| HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&ReceiveMsg::CreateOrders { splits }).unwrap(),
+        };
+
+        // = when there are no splits
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_orders_msg(vec![]),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Splits must be present.")
+        );
+
+        // = when split amounts do not add up to the amount sent in
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_orders_msg(vec![OrderSplit {
+                to: mock_token().address,
+                amount: Uint128(MOCK_AMOUNT - 1),
+            }]),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Split amounts must add up to amount.")
+        );
+
+        // = when split amounts add up to the amount sent in
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_orders_msg(vec![
+                OrderSplit {
+                    to: mock_token().address,
+                    amount: Uint128(MOCK_AMOUNT / 4),
+                },
+                OrderSplit {
+                    to: mock_user_address(),
+                    amount: Uint128(MOCK_AMOUNT - MOCK_AMOUNT / 4),
+                },
+            ]),
+        )
+        .unwrap();
+        // = * it creates an order for each split
+        let user_canonical: CanonicalAddr =
+            deps.api.canonical_address(&mock_user_address()).unwrap();
+        let first_order: Order = order_at_position(&deps.storage, &user_canonical, 0).unwrap();
+        let second_order: Order = order_at_position(&deps.storage, &user_canonical, 1).unwrap();
+        assert_eq!(first_order.to, mock_token().address);
+        assert_eq!(first_order.amount, Uint128(MOCK_AMOUNT / 4));
+        assert_eq!(second_order.to, mock_user_address());
+        assert_eq!(second_order.amount, Uint128(MOCK_AMOUNT - MOCK_AMOUNT / 4));
+        assert_eq!(
+            storage_count(
+                &deps.storage,
+                &deps
+                    .api
+                    .canonical_address(&mock_contract().address)
+                    .unwrap(),
+                PREFIX_ORDERS_COUNT
+            )
+            .unwrap(),
+            2
+        );
+        // = * it lets the execution fees for the orders be paid with one send
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(mock_execution_fee().u128() * 2),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        )
+        .unwrap();
+        let first_order: Order = order_at_position(&deps.storage, &user_canonical, 0).unwrap();
+        let second_order: Order = order_at_position(&deps.storage, &user_canonical, 1).unwrap();
+        assert_eq!(first_order.execution_fee, Some(mock_execution_fee()));
+        assert_eq!(second_order.execution_fee, Some(mock_execution_fee()));
+    }
+
+    #[test]
+    fn test_create_order_for_owner() {
+        let (_init_result, mut deps) = init_helper(true);
+        let owner: HumanAddr = HumanAddr::from("mock-owner-address");
+        let owner_canonical: CanonicalAddr = deps.api.canonical_address(&owner).unwrap();
+
+        // = when an owner is given
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: Some(owner.clone()),
+                    memo: None,
+                })
+                .unwrap(),
+            },
+        )
+        .unwrap();
+        // = * it stores the order under the owner instead of the sender
+        let creator_order: Order = order_at_position(&deps.storage, &owner_canonical, 0).unwrap();
+        assert_eq!(creator_order.creator, owner_canonical);
+        assert_eq!(
+            creator_order.sender,
+            deps.api.canonical_address(&mock_user_address()).unwrap()
+        );
+        assert_eq!(
+            storage_count(
+                &deps.storage,
+                &deps.api.canonical_address(&mock_user_address()).unwrap(),
+                PREFIX_ORDERS_COUNT
+            )
+            .unwrap(),
+            0
+        );
+        // = * it lets the sender pay the execution fee for the owner's order
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder {
+                    owner: Some(owner.clone()),
+                })
+                .unwrap(),
+            },
+        )
+        .unwrap();
+        let creator_order: Order = order_at_position(&deps.storage, &owner_canonical, 0).unwrap();
+        assert_eq!(creator_order.execution_fee, Some(mock_execution_fee()));
+        // = * it does not let the sender cancel the order
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
         );
-
-        // = when called by an admin
-        // == when order in order_positions does not exist
-        handle_msg = HandleMsg::ChangeOrdersToProcessing {
-            order_positions: vec![cosmwasm_std::Uint128(0), cosmwasm_std::Uint128(1)],
-        };
-        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        // == * it raises an error
         assert_eq!(
             handle_result.unwrap_err(),
             NotFound {
@@ -795,879 +5724,2038 @@ mod tests {
                 backtrace: None
             }
         );
+        // = * it lets the owner cancel the order and refunds the owner
+        let handle_result = handle(
+            &mut deps,
+            mock_env(owner.clone(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap().messages,
+            vec![
+                transfer_msg(
+                    owner.clone(),
+                    Uint128(MOCK_AMOUNT),
+                    Some("Refund for cancelled order 0".to_string()),
+                    mock_butt().contract_hash,
+                    mock_butt().address,
+                )
+                .unwrap(),
+                transfer_msg(
+                    owner.clone(),
+                    mock_execution_fee(),
+                    Some("Execution fee refund for cancelled order 0".to_string()),
+                    mock_sscrt().contract_hash,
+                    mock_sscrt().address,
+                )
+                .unwrap()
+            ]
+        );
 
-        // == when order in order_positions exists
-        create_order_helper(&mut deps);
-        create_order_helper(&mut deps);
-        // === when order is open
-        // ==== when order does not have an execution fee
-        // ==== * it does not set the order status to processing
-        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone()).unwrap();
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            1,
+        // = when allowlist is enabled and only the owner is on it
+        let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        config.allowlist_enabled = true;
+        TypedStoreMut::attach(&mut deps.storage)
+            .store(CONFIG_KEY, &config)
+            .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddToAllowlist {
+                addresses: vec![owner.clone()],
+            },
         )
         .unwrap();
-        let mut contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
+        // = * it checks the sender against the allowlist
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: Some(owner),
+                    memo: None,
+                })
                 .unwrap(),
-            1,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Address is not on the allowlist.")
+        );
+    }
+
+    #[test]
+    fn test_create_order_with_memo() {
+        let (_init_result, mut deps) = init_helper(true);
+        let create_order_msg = |memo: String| HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&ReceiveMsg::CreateOrder {
+                to: mock_token().address,
+                fee_waiver_code: None,
+                owner: None,
+                memo: Some(memo),
+            })
+            .unwrap(),
+        };
+
+        // = when memo is longer than the maximum length
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_msg("a".repeat(MAX_MEMO_LENGTH + 1)),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Memo must be 128 bytes or less.")
+        );
+
+        // = when memo is within the maximum length
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_msg("invoice-1".to_string()),
+        );
+        // = * it pads the response to the block size
+        assert_eq!(handle_result.unwrap().data.unwrap().len() % BLOCK_SIZE, 0);
+        // = * it stores the memo on the creator and contract orders
+        let creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
         )
         .unwrap();
-        assert_eq!(creator_order.status, 0);
-        assert_eq!(contract_order.status, 0);
-
-        // ==== when order has an execution fee
-        creator_order.execution_fee = Some(cosmwasm_std::Uint128(1));
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
+        let contract_order: Order = order_at_position(
+            &deps.storage,
             &deps
                 .api
                 .canonical_address(&mock_contract().address)
                 .unwrap(),
+            0,
         )
         .unwrap();
-        // ==== * it sets the order status to processing
+        assert_eq!(creator_order.memo, Some("invoice-1".to_string()));
+        assert_eq!(contract_order.memo, Some("invoice-1".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_state() {
+        let (_init_result, mut deps) = init_helper(false);
+        let handle_msg = HandleMsg::MigrateState { limit: Uint128(1) };
+
+        // = when contract state is up to date
+        // = * it raises an error
+        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract state is already up to date.")
+        );
+
+        // = when contract state is from version 1
+        deps.storage.remove(CONTRACT_VERSION_KEY);
+        let config_v1: ConfigV1 = ConfigV1 {
+            admin: HumanAddr::from(MOCK_ADMIN),
+            butt: mock_butt(),
+            mount_doom: mock_mount_doom(),
+            execution_fee: mock_execution_fee(),
+            sscrt: mock_sscrt(),
+            total_sent_to_mount_doom: Uint128(MOCK_AMOUNT),
+        };
+        TypedStoreMut::attach(&mut deps.storage)
+            .store(CONFIG_KEY, &config_v1)
+            .unwrap();
+        let butt_canonical: CanonicalAddr =
+            deps.api.canonical_address(&mock_butt().address).unwrap();
+        {
+            let mut registered_tokens_storage =
+                PrefixedStorage::new(PREFIX_REGISTERED_TOKENS, &mut deps.storage);
+            TypedStoreMut::attach(&mut registered_tokens_storage)
+                .store(
+                    butt_canonical.as_slice(),
+                    &RegisteredTokenV1 {
+                        address: mock_butt().address,
+                        contract_hash: mock_butt().contract_hash,
+                    },
+                )
+                .unwrap();
+        }
+        let user_canonical: CanonicalAddr =
+            deps.api.canonical_address(&mock_user_address()).unwrap();
+        let contract_canonical: CanonicalAddr = deps
+            .api
+            .canonical_address(&mock_contract().address)
+            .unwrap();
+        for (position, status) in [(0u128, 2u8), (1u128, 0u8)].iter() {
+            let order_v1: OrderV1 = OrderV1 {
+                position: Uint128(*position),
+                execution_fee: Some(mock_execution_fee()),
+                other_storage_position: Uint128(*position),
+                creator: user_canonical.clone(),
+                amount: Uint128(MOCK_AMOUNT),
+                to: mock_token().address,
+                status: *status,
+                azero_transaction_hash: if *status == 2 {
+                    Some("mock_azero_transaction_hash".to_string())
+                } else {
+                    None
+                },
+                created_at_block_time: 0,
+                created_at_block_height: 0,
+            };
+            for address in [&user_canonical, &contract_canonical].iter() {
+                let mut orders_storage = PrefixedStorage::multilevel(
+                    &[PREFIX_ORDERS, address.as_slice()],
+                    &mut deps.storage,
+                );
+                TypedStoreMut::attach(&mut orders_storage)
+                    .store(&position.to_le_bytes(), &order_v1)
+                    .unwrap();
+                set_count(
+                    &mut deps.storage,
+                    address,
+                    PREFIX_ORDERS_COUNT,
+                    position + 1,
+                )
+                .unwrap();
+            }
+        }
+        // == when called by a non-admin
+        // == * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            handle_msg.clone(),
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+        // == when called by the admin
+        // === when there are more orders than the limit
         handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone()).unwrap();
-        creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            1,
-        )
-        .unwrap();
-        contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
+        // === * it upgrades the config and registered tokens
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config, config_v1.clone().upgrade());
+        let butt: RegisteredToken = registered_token(&deps.storage, &butt_canonical).unwrap();
+        assert!(butt.enabled);
+        assert_eq!(butt.mount_doom, Some(mock_mount_doom()));
+        assert_eq!(butt.total_sent_to_mount_doom, Uint128(MOCK_AMOUNT));
+        // === * it upgrades orders up to the limit
+        let creator_order: Order = order_at_position(&deps.storage, &user_canonical, 0).unwrap();
+        assert_eq!(creator_order.token, mock_butt().address);
+        assert_eq!(creator_order.filled_amount, Uint128(MOCK_AMOUNT));
+        assert_eq!(creator_order.fills.len(), 1);
+        assert_eq!(read_contract_version(&deps.storage), 1);
+        // === * it rejects other messages until the migration is done
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
                 .unwrap(),
-            1,
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract state is being migrated.")
+        );
+        // === when the rest of the orders are within the limit
+        // === * it only upgrades the orders counted in the first transaction
+        set_count(
+            &mut deps.storage,
+            &contract_canonical,
+            PREFIX_ORDERS_COUNT,
+            3,
         )
         .unwrap();
-        assert_eq!(creator_order.status, 1);
-        assert_eq!(contract_order.status, 1);
+        handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg).unwrap();
+        // === * it upgrades the rest of the orders
+        let contract_order: Order =
+            order_at_position(&deps.storage, &contract_canonical, 1).unwrap();
+        assert_eq!(contract_order.token, mock_butt().address);
+        assert_eq!(contract_order.filled_amount, Uint128(0));
+        assert_eq!(contract_order.status, 0);
+        // === * it sets the contract version to the current version
+        assert_eq!(read_contract_version(&deps.storage), CONTRACT_VERSION);
     }
 
     #[test]
-    fn test_set_execution_fee_for_order() {
+    fn test_export_and_import_state() {
         let (_init_result, mut deps) = init_helper(true);
-        let mut env = mock_env(mock_butt().address, &[]);
-
-        // when token sent in is not sscrt
-        let receive_msg = ReceiveMsg::SetExecutionFeeForOrder {};
-        let handle_msg = HandleMsg::Receive {
-            sender: mock_user_address(),
-            from: mock_user_address(),
+        for _ in 0..2 {
+            handle(
+                &mut deps,
+                mock_env(mock_butt().address, &[]),
+                HandleMsg::Receive {
+                    sender: mock_user_address(),
+                    from: mock_user_address(),
+                    amount: Uint128(MOCK_AMOUNT),
+                    msg: to_binary(&ReceiveMsg::CreateOrder {
+                        to: mock_token().address,
+                        fee_waiver_code: None,
+                        owner: None,
+                        memo: None,
+                    })
+                    .unwrap(),
+                },
+            )
+            .unwrap();
+        }
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::ChangeRelayer {
+                relayer: Some(mock_relayer()),
+            },
+            None,
+        )
+        .unwrap();
+        let admin_canonical: CanonicalAddr = deps
+            .api
+            .canonical_address(&HumanAddr::from(MOCK_ADMIN))
+            .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddToAllowlist {
+                addresses: vec![mock_user_address()],
+            },
+        )
+        .unwrap();
+        let fee_waivers: Vec<FeeWaiver> = vec![FeeWaiver {
+            address: mock_user_address(),
+            discount_basis_points: 5_000,
+        }];
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddFeeWaivers {
+                fee_waivers: fee_waivers.clone(),
+            },
+        )
+        .unwrap();
+        let fee_waiver_codes: Vec<FeeWaiverCode> = vec![FeeWaiverCode {
+            code_hash: Binary::from(sha_256(b"mock-fee-waiver-code").to_vec()),
+            discount_basis_points: 10_000,
+        }];
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::AddFeeWaiverCodes {
+                fee_waiver_codes: fee_waiver_codes.clone(),
+            },
+        )
+        .unwrap();
+        let accrued_fees: Vec<AccruedFee> = vec![AccruedFee {
+            token: Some(mock_sscrt().address),
             amount: Uint128(1),
-            msg: to_binary(&receive_msg).unwrap(),
+        }];
+        write_accrued_fees(&mut deps.storage, &admin_canonical, &accrued_fees).unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::UpdateExecutionFee {
+                    execution_fee: Uint128(1),
+                },
+            },
+        )
+        .unwrap();
+        let pending_admin_operations: Vec<PendingAdminOperation> =
+            read_pending_admin_operations(&deps.storage);
+        let export_state = |deps: &Extern<MockStorage, MockApi, MockQuerier>,
+                            start: u128,
+                            checksum: Option<Binary>| {
+            let query_result = query(
+                deps,
+                QueryMsg::ExportState {
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    start: Uint128(start),
+                    limit: Uint128(1),
+                    checksum,
+                },
+            );
+            from_binary(&query_result.unwrap()).unwrap()
         };
-        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
-        // * it raises an error
+
+        // = when exporting state before the contract is frozen
+        // = * it raises an error
+        let query_result = query(
+            &deps,
+            QueryMsg::ExportState {
+                key: MOCK_VIEWING_KEY.to_string(),
+                start: Uint128(0),
+                limit: Uint128(1),
+                checksum: None,
+            },
+        );
+        assert_eq!(
+            query_result.unwrap_err(),
+            StdError::generic_err("Contract must be frozen before its state is exported.")
+        );
+
+        // = when exporting state a page at a time after the contract is frozen
+        // = * it returns the config, registered tokens, the rest of the state and a page of
+        //     contract orders
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::Freeze {
+                new_instance: HumanAddr::from("new-instance"),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        )
+        .unwrap();
+        let first_page: QueryAnswer = export_state(&deps, 0, None);
+        let (mut config, registered_tokens, state, first_orders, first_checksum) = match first_page
+        {
+            QueryAnswer::ExportState {
+                config,
+                contract_version,
+                registered_tokens,
+                state,
+                orders,
+                total,
+                checksum,
+            } => {
+                assert_eq!(contract_version, CONTRACT_VERSION);
+                assert_eq!(total, Uint128(2));
+                assert_eq!(orders.len(), 1);
+                (config, registered_tokens, state, orders, checksum)
+            }
+            _ => panic!("Unexpected"),
+        };
+        assert_eq!(registered_tokens.len(), 2);
+        // = * it leaves the destinations out of the orders
+        assert_eq!(first_orders[0].to, HumanAddr::default());
+        assert_eq!(
+            state,
+            ExportedState {
+                accrued_fees: vec![FeeRecipientAccruedFees {
+                    fee_recipient: HumanAddr::from(MOCK_ADMIN),
+                    accrued_fees: accrued_fees.clone(),
+                }],
+                admin_operations_count: Uint128(3),
+                allowlist: vec![mock_user_address()],
+                fee_waiver_codes: fee_waiver_codes.clone(),
+                fee_waivers: fee_waivers.clone(),
+                pending_admin_operations: pending_admin_operations.clone(),
+            }
+        );
+        let second_page: QueryAnswer = export_state(&deps, 1, Some(first_checksum.clone()));
+        let (second_orders, second_checksum) = match second_page {
+            QueryAnswer::ExportState {
+                orders, checksum, ..
+            } => (orders, checksum),
+            _ => panic!("Unexpected"),
+        };
+
+        // = when the instance was not initialised to import state
+        // = * it raises an error
+        let (_init_result, mut other_deps) = init_helper(false);
+        let handle_result = handle(
+            &mut other_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: Some(config.clone()),
+                registered_tokens: registered_tokens.clone(),
+                state: Some(state.clone()),
+                orders: first_orders.clone(),
+                checksum: first_checksum.clone(),
+            },
+        );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Execution fee token must be SSCRT.")
+            StdError::generic_err("Contract is not importing state.")
         );
 
-        // when token sent in is sscrt
-        env = mock_env(mock_sscrt().address, &[]);
-        // = when amount sent in is not equal to execution fee
-        let handle_result = handle(&mut deps, env.clone(), handle_msg);
-        // * it raises an error
+        // = when importing state into a fresh instance initialised to import state
+        let mut new_deps = mock_dependencies(20, &[]);
+        init(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            InitMsg {
+                allowlist_enabled: None,
+                burn_tokens: None,
+                butt: mock_butt(),
+                execution_fee: mock_execution_fee(),
+                mount_doom: mock_mount_doom(),
+                sscrt: mock_sscrt(),
+                transfer_memos_enabled: None,
+                relayer: None,
+                importing: Some(true),
+            },
+        )
+        .unwrap();
+        // == when tokens are received before the import is finalised
+        // == * it raises an error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
+                .unwrap(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract is importing state.")
+        );
+        // == when called by a non-admin
+        // == * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::ImportState {
+                config: Some(config.clone()),
+                registered_tokens: registered_tokens.clone(),
+                state: Some(state.clone()),
+                orders: first_orders.clone(),
+                checksum: first_checksum.clone(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+        // == when pages are imported out of order
+        // == * it raises an error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: None,
+                registered_tokens: vec![],
+                state: None,
+                orders: second_orders.clone(),
+                checksum: second_checksum.clone(),
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Orders must be imported in order of position.")
+        );
+        // == when the config has no fee recipients
+        // == * it raises an error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: Some(Config {
+                    fee_recipients: vec![],
+                    ..config.clone()
+                }),
+                registered_tokens: vec![],
+                state: None,
+                orders: vec![],
+                checksum: Binary(vec![]),
+            },
+        );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Amount sent in must equal execution fee.")
+            StdError::generic_err(
+                "Fee recipients must be present and have weights greater than zero."
+            )
+        );
+        // == when the checksum does not match
+        // == * it raises an error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: Some(config.clone()),
+                registered_tokens: registered_tokens.clone(),
+                state: Some(state.clone()),
+                orders: first_orders.clone(),
+                checksum: second_checksum.clone(),
+            },
         );
-        // = when amount sent in is equal to execution fee
-        let handle_msg = HandleMsg::Receive {
-            sender: mock_user_address(),
-            from: mock_user_address(),
-            amount: mock_execution_fee(),
-            msg: to_binary(&receive_msg).unwrap(),
-        };
-        let handle_result = handle(&mut deps, env.clone(), handle_msg);
-        // == when user does not have any orders
-        // === * it raises an error
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Order does not exist.")
+            StdError::generic_err("Checksum does not match.")
         );
-        // == when user has at least one order
-        create_order_helper(&mut deps);
-        // === when current block is the same as the block when the order is created
-        // ==== when order has fee set already
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
+        // == when pages are imported in order with matching checksums
+        // The new instance has none of the SCRT that the config says is held
+        config.scrt_execution_fees_held = Uint128(1);
+        handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: Some(config.clone()),
+                registered_tokens: registered_tokens.clone(),
+                state: Some(state),
+                orders: first_orders,
+                checksum: first_checksum,
+            },
         )
         .unwrap();
-        creator_order.execution_fee = Some(Uint128(1));
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: None,
+                registered_tokens: vec![],
+                state: None,
+                orders: second_orders,
+                checksum: second_checksum,
+            },
         )
         .unwrap();
-        // ==== * it raises an error
-        let handle_msg = HandleMsg::Receive {
-            sender: mock_user_address(),
-            from: mock_user_address(),
-            amount: mock_execution_fee(),
-            msg: to_binary(&receive_msg).unwrap(),
+        // == * it stores the config with the SCRT held reconciled with the balance
+        let new_config: Config = TypedStore::attach(&new_deps.storage)
+            .load(CONFIG_KEY)
+            .unwrap();
+        assert_eq!(
+            new_config,
+            Config {
+                scrt_execution_fees_held: Uint128(0),
+                ..config
+            }
+        );
+        // == * it stores the registered tokens
+        assert_eq!(
+            read_registered_token_addresses(&new_deps.storage),
+            vec![mock_butt().address, mock_token().address]
+        );
+        // == * it stores the rest of the state
+        assert_eq!(
+            read_accrued_fees(&new_deps.storage, &admin_canonical).unwrap(),
+            accrued_fees
+        );
+        let user_canonical: CanonicalAddr =
+            deps.api.canonical_address(&mock_user_address()).unwrap();
+        assert!(read_allowlisted(&new_deps.storage, &user_canonical));
+        assert_eq!(
+            read_fee_waiver(&new_deps.storage, &user_canonical),
+            Some(5_000)
+        );
+        assert_eq!(
+            read_fee_waiver_code(&new_deps.storage, &sha_256(b"mock-fee-waiver-code")),
+            Some(10_000)
+        );
+        assert_eq!(
+            read_pending_admin_operations(&new_deps.storage),
+            pending_admin_operations
+        );
+        assert_eq!(read_admin_operations_count(&new_deps.storage), 3);
+        // == * it stores the orders without their destinations
+        let contract_canonical: CanonicalAddr = deps
+            .api
+            .canonical_address(&mock_contract().address)
+            .unwrap();
+        assert_eq!(
+            order_at_position(&new_deps.storage, &contract_canonical, 0)
+                .unwrap()
+                .to,
+            HumanAddr::default()
+        );
+
+        // = when importing destinations
+        let destinations: Vec<OrderDestination> = match from_binary(
+            &query(
+                &deps,
+                QueryMsg::RelayerOrders {
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    filter: OrderFilter::default(),
+                    ascending: Some(true),
+                    start_after: None,
+                    limit: Uint128(MAX_PAGE_SIZE),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::RelayerOrders { orders, .. } => orders
+                .into_iter()
+                .map(|order| OrderDestination {
+                    position: order.position,
+                    to: order.to,
+                })
+                .collect(),
+            _ => panic!("Unexpected"),
         };
-        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // == when called by someone other than the relayer
+        // == * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportDestinations {
+                destinations: destinations.clone(),
+            },
+        );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Execution fee already set for order.")
+            StdError::Unauthorized { backtrace: None }
         );
-        // ==== when order does not have execution fee set already
-        // ===== when order is cancelled
-        creator_order.execution_fee = None;
-        creator_order.status = 3;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        // == when called by the relayer with the destinations of the frozen instance
+        handle(
+            &mut new_deps,
+            mock_env(mock_relayer(), &[]),
+            HandleMsg::ImportDestinations {
+                destinations: destinations.clone(),
+            },
         )
         .unwrap();
-        // ===== * it raises an error
-        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // == * it stores the contract and creator copies of the orders with their destinations
+        for address in [&user_canonical, &contract_canonical].iter() {
+            assert_eq!(
+                storage_count(&new_deps.storage, address, PREFIX_ORDERS_COUNT).unwrap(),
+                2
+            );
+            for position in 0..2 {
+                assert_eq!(
+                    order_at_position(&new_deps.storage, address, position).unwrap(),
+                    order_at_position(&deps.storage, address, position).unwrap()
+                );
+            }
+        }
+
+        // = when finalising the import
+        // == when called by a non-admin
+        // == * it raises an Unauthorized error
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::FinaliseImport {},
+        );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Order is not open.")
+            StdError::Unauthorized { backtrace: None }
         );
-        // ===== when order is filled
-        creator_order.status = 2;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        // == when called by the admin
+        handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FinaliseImport {},
         )
         .unwrap();
-        // ===== * it raises an error
-        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
+        // == * it stops state from being imported again
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ImportState {
+                config: None,
+                registered_tokens: vec![],
+                state: None,
+                orders: vec![],
+                checksum: Binary(vec![]),
+            },
+        );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("Order is not open.")
+            StdError::generic_err("Contract is not importing state.")
         );
-        // ===== when order is open
-        creator_order.status = 0;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(mock_relayer(), &[]),
+            HandleMsg::ImportDestinations { destinations },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract is not importing state.")
+        );
+        let handle_result = handle(
+            &mut new_deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FinaliseImport {},
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract is not importing state.")
+        );
+    }
+
+    #[test]
+    fn test_handle_logs() {
+        let (_init_result, mut deps) = init_helper(true);
+        // Another user's order puts the user's positions behind the contract's
+        let other_user_address: HumanAddr = HumanAddr::from("bob");
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: other_user_address.clone(),
+                from: other_user_address,
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
                 .unwrap(),
+            },
         )
         .unwrap();
-        // ===== * it sets the execution fee for the user order
-        let handle_unwrapped = handle(&mut deps, env.clone(), handle_msg.clone()).unwrap();
-        creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
-        )
-        .unwrap();
-        assert_eq!(creator_order.execution_fee, Some(mock_execution_fee()));
-        // ===== * it sets the execution fee for the contract order
-        let contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-            creator_order.other_storage_position.u128(),
+        let create_order_msg = HandleMsg::Receive {
+            sender: mock_user_address(),
+            from: mock_user_address(),
+            amount: Uint128(MOCK_AMOUNT),
+            msg: to_binary(&ReceiveMsg::CreateOrder {
+                to: mock_token().address,
+                fee_waiver_code: None,
+                owner: None,
+                memo: Some("invoice-1".to_string()),
+            })
+            .unwrap(),
+        };
+
+        // = when an order is created
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_msg.clone(),
+        );
+        // = * it logs the action, creator and contract positions, status, amount and fee without
+        //     the destination or memo
+        assert_eq!(
+            handle_result.unwrap().log,
+            vec![
+                log("action", "create_order"),
+                log("creator_position", 0),
+                log("contract_position", 1),
+                log("status", "open"),
+                log("amount", MOCK_AMOUNT),
+                log("fee", 0),
+            ]
+        );
+
+        // = when an order is cancelled
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
+        );
+        // = * it logs the status transition
+        assert_eq!(
+            handle_result.unwrap().log,
+            vec![
+                log("action", "cancel_order"),
+                log("creator_position", 0),
+                log("contract_position", 1),
+                log("previous_status", "open"),
+                log("status", "cancelled"),
+                log("amount", MOCK_AMOUNT),
+                log("fee", 0),
+            ]
+        );
+
+        // = when an order is changed to processing and filled
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            create_order_msg,
         )
         .unwrap();
-        assert_eq!(contract_order.execution_fee, Some(mock_execution_fee()));
-        // ===== * it sends the humanized creator order back as data
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        );
+        // = * it logs the execution fee set
         assert_eq!(
-            handle_unwrapped.data,
-            pad_response(Ok(HandleResponse {
-                messages: vec![],
-                log: vec![],
-                data: Some(
-                    to_binary(&creator_order.clone().into_humanized(&deps.api).unwrap()).unwrap()
-                ),
-            }))
-            .unwrap()
-            .data
+            handle_result.unwrap().log,
+            vec![
+                log("action", "set_execution_fee_for_order"),
+                log("creator_position", 1),
+                log("contract_position", 2),
+                log("fee", mock_execution_fee()),
+            ]
+        );
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::UpdateOrderDestination {
+                position: Uint128(1),
+                to: mock_user_address(),
+            },
+        );
+        // = * it logs the destination update without the destination
+        assert_eq!(
+            handle_result.unwrap().log,
+            vec![
+                log("action", "update_order_destination"),
+                log("creator_position", 1),
+                log("contract_position", 2),
+            ]
+        );
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(2)],
+            },
+        );
+        // = * it logs each order changed to processing
+        assert_eq!(
+            handle_result.unwrap().log,
+            vec![
+                log("action", "change_orders_to_processing"),
+                log("creator_position", 1),
+                log("contract_position", 2),
+                log("previous_status", "open"),
+                log("status", "processing"),
+            ]
+        );
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(2),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
         );
+        // = * it logs each fill with its amount, fee and Aleph Zero transaction hash
+        assert_eq!(
+            handle_result.unwrap().log,
+            vec![
+                log("action", "fill_orders"),
+                log("creator_position", 1),
+                log("contract_position", 2),
+                log("previous_status", "processing"),
+                log("status", "filled"),
+                log("amount", MOCK_AMOUNT),
+                log("azero_transaction_hash", "mock_azero_transaction_hash"),
+                log("fee", mock_execution_fee()),
+            ]
+        );
+    }
 
-        // === when current block is different from the block when the order is created
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
+    #[test]
+    fn test_admin_handle_answers() {
+        let (_init_result, mut deps) = init_helper(false);
+
+        // = when tokens are registered
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::RegisterTokens {
+                tokens: vec![mock_butt(), mock_token()],
+                viewing_key: MOCK_VIEWING_KEY.to_string(),
+            },
+        );
+        // = * it sends back the newly registered tokens, padded to the block size
+        let data: Binary = handle_result.unwrap().data.unwrap();
+        assert_eq!(data.len() % BLOCK_SIZE, 0);
+        match from_binary(&data).unwrap() {
+            HandleAnswer::RegisterTokens { registered_tokens } => {
+                assert_eq!(
+                    registered_tokens,
+                    vec![mock_butt().address, mock_token().address]
+                )
+            }
+            _ => panic!("Unexpected"),
+        }
+
+        // = when the config is updated
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateConfig {
+                allowlist_enabled: None,
+                transfer_memos_enabled: Some(false),
+            },
+        );
+        // = * it sends back the updated config
+        match from_binary(&handle_result.unwrap().data.unwrap()).unwrap() {
+            HandleAnswer::UpdateConfig { config } => assert!(!config.transfer_memos_enabled),
+            _ => panic!("Unexpected"),
+        }
+
+        // = when orders are changed to processing and filled
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
+                .unwrap(),
+            },
         )
         .unwrap();
-        creator_order.created_at_block_height = 1;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
         )
         .unwrap();
-        // === * it raises an error
-        let handle_result = handle(&mut deps, env.clone(), handle_msg);
-        assert_eq!(
-            handle_result.unwrap_err(),
-            StdError::generic_err(
-                "Execution fee must be set at the same block as when order is created."
-            )
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        );
+        // = * it sends back the orders changed to processing
+        match from_binary(&handle_result.unwrap().data.unwrap()).unwrap() {
+            HandleAnswer::ChangeOrdersToProcessing { orders } => {
+                assert_eq!(orders.len(), 1);
+                assert_eq!(orders[0].status, 1);
+            }
+            _ => panic!("Unexpected"),
+        }
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
         );
+        // = * it sends back the filled orders, amounts sent to Mount Doom and fees accrued
+        match from_binary(&handle_result.unwrap().data.unwrap()).unwrap() {
+            HandleAnswer::FillOrders {
+                orders,
+                burned,
+                sent_to_mount_doom,
+                fees_accrued,
+            } => {
+                assert_eq!(orders[0].status, 2);
+                assert_eq!(burned, vec![]);
+                assert_eq!(
+                    sent_to_mount_doom,
+                    vec![TokenAmount {
+                        token: mock_butt().address,
+                        amount: Uint128(MOCK_AMOUNT),
+                    }]
+                );
+                assert_eq!(
+                    fees_accrued,
+                    vec![AccruedFee {
+                        token: Some(mock_sscrt().address),
+                        amount: mock_execution_fee(),
+                    }]
+                );
+            }
+            _ => panic!("Unexpected"),
+        }
     }
 
     #[test]
-    fn test_cancel_order() {
+    fn test_transfer_memos() {
         let (_init_result, mut deps) = init_helper(true);
-        let env = mock_env(mock_user_address(), &[]);
-
-        // = when order at position does not exist
-        let mut handle_msg = HandleMsg::CancelOrder {
-            position: Uint128(0),
-        };
-        let mut handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
-
-        // = * it raises an error
-        assert_eq!(
-            handle_result.unwrap_err(),
-            NotFound {
-                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
-                backtrace: None
-            }
-        );
 
-        // = when order at position exists
-        create_order_helper(&mut deps);
-        handle_msg = HandleMsg::CancelOrder {
-            position: Uint128(0),
-        };
-        // === when order is cancelled
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
+        // = when there is a memo
+        // = * it sends a transfer with the memo to the token, padded to the block size
+        let cosmos_msg: CosmosMsg = transfer_msg(
+            mock_user_address(),
+            Uint128(MOCK_AMOUNT),
+            Some("Refund for cancelled order 0".to_string()),
+            mock_butt().contract_hash,
+            mock_butt().address,
         )
         .unwrap();
-        creator_order.status = 3;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        match cosmos_msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                callback_code_hash,
+                msg,
+                send,
+            }) => {
+                assert_eq!(contract_addr, mock_butt().address);
+                assert_eq!(callback_code_hash, mock_butt().contract_hash);
+                assert_eq!(msg.len() % BLOCK_SIZE, 0);
+                assert_eq!(
+                    from_binary::<Snip20HandleMsg>(&msg).unwrap(),
+                    Snip20HandleMsg::Transfer {
+                        recipient: mock_user_address(),
+                        amount: Uint128(MOCK_AMOUNT),
+                        memo: Some("Refund for cancelled order 0".to_string()),
+                    }
+                );
+                assert_eq!(send, vec![]);
+            }
+            _ => panic!("Unexpected"),
+        }
+
+        // = when transfer memos are disabled
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::UpdateConfig {
+                allowlist_enabled: None,
+                transfer_memos_enabled: Some(false),
+            },
         )
         .unwrap();
-        // === * it raises an error
-        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
-        assert_eq!(
-            handle_result.unwrap_err(),
-            StdError::generic_err("Order can only be cancelled if open.")
-        );
-        // === when order is filled
-        creator_order.status = 2;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
+        handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
                 .unwrap(),
+            },
         )
         .unwrap();
-        // === * it raises an error
-        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
-        assert_eq!(
-            handle_result.unwrap_err(),
-            StdError::generic_err("Order can only be cancelled if open.")
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
         );
-        // === when order can be cancelled
-        creator_order.status = 0;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-        )
-        .unwrap();
-        handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
-        // === * it sends the unfilled from token amount back to the creator
-        let handle_result_unwrapped = handle_result.unwrap();
+        // = * it sends transfers without a memo
         assert_eq!(
-            handle_result_unwrapped.messages,
+            handle_result.unwrap().messages,
             vec![snip20::transfer_msg(
-                deps.api.human_address(&creator_order.creator).unwrap(),
-                creator_order.amount,
+                mock_user_address(),
+                Uint128(MOCK_AMOUNT),
                 None,
                 BLOCK_SIZE,
-                mock_butt().contract_hash.clone(),
-                mock_butt().address.clone(),
+                mock_butt().contract_hash,
+                mock_butt().address,
             )
             .unwrap()]
         );
-        // === * it sends the creator order as humanized back as data
-        let creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
+    }
+
+    #[test]
+    fn test_filtered_orders() {
+        let (_init_result, mut deps) = init_helper(true);
+        for _ in 0..3 {
+            create_order_helper(&mut deps);
+        }
+        handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(1),
+            },
         )
         .unwrap();
+        let filtered_orders = |deps: &Extern<MockStorage, MockApi, MockQuerier>,
+                               filter: OrderFilter,
+                               ascending: Option<bool>,
+                               start_after: Option<Uint128>,
+                               limit: u128| {
+            let query_result = query(
+                deps,
+                QueryMsg::FilteredOrders {
+                    address: mock_user_address(),
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    filter,
+                    ascending,
+                    start_after,
+                    limit: Uint128(limit),
+                },
+            )
+            .unwrap();
+            assert_eq!(query_result.len() % BLOCK_SIZE, 0);
+            match from_binary(&query_result).unwrap() {
+                QueryAnswer::FilteredOrders {
+                    orders,
+                    next_cursor,
+                } => (
+                    orders
+                        .iter()
+                        .map(|order| order.position.u128())
+                        .collect::<Vec<u128>>(),
+                    next_cursor,
+                ),
+                _ => panic!("Unexpected"),
+            }
+        };
+        let open_filter: OrderFilter = OrderFilter {
+            statuses: Some(vec![0]),
+            ..OrderFilter::default()
+        };
+
+        // = when filtering by status newest first, a page at a time
+        // = * it returns the matching orders and a cursor to continue from
         assert_eq!(
-            handle_result_unwrapped.data,
-            pad_response(Ok(HandleResponse {
-                messages: vec![],
-                log: vec![],
-                data: Some(
-                    to_binary(&creator_order.clone().into_humanized(&deps.api).unwrap()).unwrap()
+            filtered_orders(&deps, open_filter.clone(), None, None, 1),
+            (vec![2], Some(Uint128(2)))
+        );
+        assert_eq!(
+            filtered_orders(&deps, open_filter.clone(), None, Some(Uint128(2)), 1),
+            (vec![0], None)
+        );
+
+        // = when filtering oldest first
+        // = * it returns the matching orders in ascending order
+        assert_eq!(
+            filtered_orders(&deps, open_filter, Some(true), None, 10),
+            (vec![0, 2], None)
+        );
+
+        // = when filtering by creation time and minimum amount
+        let env = mock_env(MOCK_ADMIN, &[]);
+        // = * it only returns orders created within the range with at least the minimum amount
+        assert_eq!(
+            filtered_orders(
+                &deps,
+                OrderFilter {
+                    created_after_block_height: Some(env.block.height - 1),
+                    created_before_block_time: Some(env.block.time + 1),
+                    minimum_amount: Some(Uint128(MOCK_AMOUNT)),
+                    ..OrderFilter::default()
+                },
+                Some(true),
+                None,
+                10
+            ),
+            (vec![0, 1, 2], None)
+        );
+        assert_eq!(
+            filtered_orders(
+                &deps,
+                OrderFilter {
+                    minimum_amount: Some(Uint128(MOCK_AMOUNT + 1)),
+                    ..OrderFilter::default()
+                },
+                None,
+                None,
+                10
+            ),
+            (vec![], None)
+        );
+    }
+
+    #[test]
+    fn test_order_history() {
+        let (_init_result, mut deps) = init_helper(true);
+        for _ in 0..3 {
+            create_order_helper(&mut deps);
+        }
+        let order_history = |deps: &Extern<MockStorage, MockApi, MockQuerier>,
+                             start_after: Option<Uint128>,
+                             limit: u128| {
+            query(
+                deps,
+                QueryMsg::OrderHistory {
+                    address: mock_user_address(),
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    start_after,
+                    limit: Uint128(limit),
+                },
+            )
+            .map(|query_result| match from_binary(&query_result).unwrap() {
+                QueryAnswer::OrderHistory {
+                    orders,
+                    next_cursor,
+                } => (
+                    orders
+                        .iter()
+                        .map(|order| order.position.u128())
+                        .collect::<Vec<u128>>(),
+                    next_cursor,
                 ),
-            }))
-            .unwrap()
-            .data
+                _ => panic!("Unexpected"),
+            })
+        };
+
+        // = when limit is zero
+        // = * it raises an error
+        assert_eq!(
+            order_history(&deps, None, 0).unwrap_err(),
+            StdError::generic_err("Page size must be between 1 and 100.")
         );
 
-        // === * it sets cancelled to true
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            0,
-        )
-        .unwrap();
-        let contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-            creator_order.other_storage_position.u128(),
-        )
-        .unwrap();
-        assert_eq!(creator_order.status, 3);
-        assert_eq!(contract_order.status, 3);
+        // = when limit is greater than the maximum page size
+        // = * it raises an error
+        assert_eq!(
+            order_history(&deps, None, MAX_PAGE_SIZE + 1).unwrap_err(),
+            StdError::generic_err("Page size must be between 1 and 100.")
+        );
+        assert_eq!(
+            query(
+                &deps,
+                QueryMsg::Orders {
+                    address: mock_user_address(),
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    page: Uint128(0),
+                    page_size: Uint128(MAX_PAGE_SIZE + 1),
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err("Page size must be between 1 and 100.")
+        );
 
-        // ==== when order has an execution fee
-        creator_order.execution_fee = Some(Uint128(1));
-        creator_order.status = 0;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-        )
-        .unwrap();
-        creator_order.execution_fee = Some(Uint128(1));
-        creator_order.status = 0;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-        )
-        .unwrap();
-        // ===== * it sends the execution fee back to the creator
-        handle_result = handle(&mut deps, env.clone(), handle_msg);
+        // = when paging through orders
+        // = * it returns the newest orders first with a cursor to continue from
         assert_eq!(
-            handle_result.unwrap().messages,
-            vec![
-                snip20::transfer_msg(
-                    deps.api.human_address(&creator_order.creator).unwrap(),
-                    creator_order.amount,
-                    None,
-                    BLOCK_SIZE,
-                    mock_butt().contract_hash,
-                    mock_butt().address,
-                )
-                .unwrap(),
-                snip20::transfer_msg(
-                    deps.api.human_address(&creator_order.creator).unwrap(),
-                    creator_order.execution_fee.unwrap(),
-                    None,
-                    BLOCK_SIZE,
-                    mock_sscrt().contract_hash,
-                    mock_sscrt().address,
-                )
-                .unwrap()
-            ]
+            order_history(&deps, None, 2).unwrap(),
+            (vec![2, 1], Some(Uint128(1)))
+        );
+        // == when an order is created between pages
+        create_order_helper(&mut deps);
+        // == * it continues from the cursor without repeating orders
+        assert_eq!(
+            order_history(&deps, Some(Uint128(1)), 2).unwrap(),
+            (vec![0], None)
         );
     }
 
     #[test]
-    fn test_config() {
-        let (_init_result, deps) = init_helper(false);
+    fn test_migration_stats() {
+        let (_init_result, mut deps) = init_helper(true);
+        let migration_stats = |deps: &Extern<MockStorage, MockApi, MockQuerier>| match from_binary(
+            &query(deps, QueryMsg::MigrationStats {}).unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::MigrationStats {
+                open_orders,
+                execution_fee,
+                tokens,
+            } => (open_orders, execution_fee, tokens),
+            _ => panic!("Unexpected"),
+        };
 
-        let res = query(&deps, QueryMsg::Config {}).unwrap();
-        let value: Config = from_binary(&res).unwrap();
-        assert_eq!(
-            Config {
-                admin: HumanAddr::from(MOCK_ADMIN),
-                butt: mock_butt(),
-                execution_fee: mock_execution_fee(),
-                mount_doom: mock_mount_doom(),
-                sscrt: mock_sscrt(),
-                total_sent_to_mount_doom: Uint128(0)
+        // = when orders are created and cancelled
+        for _ in 0..2 {
+            create_order_helper(&mut deps);
+        }
+        handle(
+            &mut deps,
+            mock_env(mock_user_address(), &[]),
+            HandleMsg::CancelOrder {
+                position: Uint128(1),
             },
-            value
+        )
+        .unwrap();
+        // = * it counts the orders that are still open
+        // = * it returns the fee parameters and stats of each registered token
+        let (open_orders, execution_fee, tokens) = migration_stats(&deps);
+        assert_eq!(open_orders, Uint128(1));
+        assert_eq!(execution_fee, mock_execution_fee());
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.token.clone())
+                .collect::<Vec<HumanAddr>>(),
+            vec![mock_butt().address, mock_token().address]
         );
+        assert_eq!(tokens[0].total_migrated, Uint128(0));
+
+        // = when an open order is changed to processing and filled
+        let mut creator_order: Order = order_at_position(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_user_address()).unwrap(),
+            0,
+        )
+        .unwrap();
+        creator_order.execution_fee = Some(mock_execution_fee());
+        update_creator_order_and_associated_contract_order(
+            &mut deps.storage,
+            creator_order,
+            &deps
+                .api
+                .canonical_address(&mock_contract().address)
+                .unwrap(),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
+        )
+        .unwrap();
+        // = * it no longer counts the order as open
+        // = * it adds the amount filled to the token's total migrated
+        let (open_orders, _, tokens) = migration_stats(&deps);
+        assert_eq!(open_orders, Uint128(0));
+        assert_eq!(tokens[0].total_migrated, Uint128(MOCK_AMOUNT));
+        assert_eq!(tokens[0].total_sent_to_mount_doom, Uint128(MOCK_AMOUNT));
     }
 
     #[test]
-    fn test_create_order() {
+    fn test_relayer_orders() {
         let (_init_result, mut deps) = init_helper(true);
-        let receive_msg = ReceiveMsg::CreateOrder {
-            to: mock_token().address,
-        };
-        let handle_msg = HandleMsg::Receive {
-            sender: mock_user_address(),
-            from: mock_user_address(),
-            amount: Uint128(MOCK_AMOUNT),
-            msg: to_binary(&receive_msg).unwrap(),
+        create_order_helper(&mut deps);
+        let relayer_orders = |deps: &Extern<MockStorage, MockApi, MockQuerier>| {
+            query(
+                deps,
+                QueryMsg::RelayerOrders {
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    filter: OrderFilter::default(),
+                    ascending: None,
+                    start_after: None,
+                    limit: Uint128(10),
+                },
+            )
         };
 
-        // = when token sent in isn't BUTT
-        let handle_result = handle(
-            &mut deps,
-            mock_env(mock_contract().address, &[]),
-            handle_msg.clone(),
+        // = when the contract's orders are queried with the contract's address
+        // = * it raises an error
+        let contract_orders_error =
+            StdError::generic_err("Contract orders can only be queried by the relayer.");
+        assert_eq!(
+            query(
+                &deps,
+                QueryMsg::Orders {
+                    address: mock_contract().address,
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    page: Uint128(0),
+                    page_size: Uint128(10),
+                },
+            )
+            .unwrap_err(),
+            contract_orders_error
+        );
+        assert_eq!(
+            query(
+                &deps,
+                QueryMsg::FilteredOrders {
+                    address: mock_contract().address,
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    filter: OrderFilter::default(),
+                    ascending: None,
+                    start_after: None,
+                    limit: Uint128(10),
+                },
+            )
+            .unwrap_err(),
+            contract_orders_error
         );
+        assert_eq!(
+            query(
+                &deps,
+                QueryMsg::OrderHistory {
+                    address: mock_contract().address,
+                    key: MOCK_VIEWING_KEY.to_string(),
+                    start_after: None,
+                    limit: Uint128(10),
+                },
+            )
+            .unwrap_err(),
+            contract_orders_error
+        );
+
+        // = when the relayer isn't set
         // = * it raises an error
         assert_eq!(
-            handle_result.unwrap_err(),
-            StdError::Unauthorized { backtrace: None }
+            relayer_orders(&deps).unwrap_err(),
+            StdError::generic_err("Relayer is not set.")
         );
 
-        // = when token sent in is BUTT
-        let handle_unwrapped = handle(
+        // = when the relayer is set
+        execute_admin_operation_helper(
             &mut deps,
-            mock_env(mock_butt().address, &[]),
-            handle_msg.clone(),
+            AdminOperation::ChangeRelayer {
+                relayer: Some(mock_relayer()),
+            },
+            None,
         )
         .unwrap();
-        // === * it sends the humanized creator order back as data
-        let order: Order = Order {
-            position: Uint128(0),
-            execution_fee: None,
-            other_storage_position: Uint128(0),
-            creator: deps.api.canonical_address(&mock_user_address()).unwrap(),
-            amount: Uint128(MOCK_AMOUNT),
-            to: mock_token().address,
-            status: 0,
-            azero_transaction_hash: None,
-            created_at_block_time: mock_env(MOCK_ADMIN, &[]).block.time,
-            created_at_block_height: mock_env(MOCK_ADMIN, &[]).block.height,
-        };
-        assert_eq!(
-            handle_unwrapped.data,
-            pad_response(Ok(HandleResponse {
-                messages: vec![],
-                log: vec![],
-                data: Some(to_binary(&order.clone().into_humanized(&deps.api).unwrap()).unwrap()),
-            }))
-            .unwrap()
-            .data
-        );
+        // = * it returns the contract's orders with their destinations, padded to the block size
+        let query_result = relayer_orders(&deps).unwrap();
+        assert_eq!(query_result.len() % BLOCK_SIZE, 0);
+        match from_binary(&query_result).unwrap() {
+            QueryAnswer::RelayerOrders {
+                orders,
+                next_cursor,
+            } => {
+                assert_eq!(orders.len(), 1);
+                assert_eq!(orders[0].position, Uint128(0));
+                assert_eq!(orders[0].to, mock_token().address);
+                assert_eq!(orders[0].amount, Uint128(MOCK_AMOUNT));
+                assert_eq!(next_cursor, None);
+            }
+            _ => panic!("Unexpected"),
+        }
 
-        // === * it stores the order for the creator
-        // === * it stores the order for the smart_contract
+        // = when the relayer changes orders to processing and fills them
+        handle(
+            &mut deps,
+            mock_env(mock_sscrt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: mock_execution_fee(),
+                msg: to_binary(&ReceiveMsg::SetExecutionFeeForOrder { owner: None }).unwrap(),
+            },
+        )
+        .unwrap();
+        // = * it raises an error for anyone other than the admin and relayer
         assert_eq!(
-            order_at_position(
-                &mut deps.storage,
-                &deps.api.canonical_address(&mock_user_address()).unwrap(),
-                0
+            handle(
+                &mut deps,
+                mock_env(mock_user_address(), &[]),
+                HandleMsg::ChangeOrdersToProcessing {
+                    order_positions: vec![Uint128(0)],
+                },
             )
-            .unwrap(),
-            order
+            .unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
         );
-        assert_eq!(
-            order_at_position(
-                &mut deps.storage,
-                &deps
-                    .api
-                    .canonical_address(&mock_contract().address)
-                    .unwrap(),
-                0
-            )
-            .unwrap(),
-            order
+        // = * it changes and fills the orders for the relayer
+        handle(
+            &mut deps,
+            mock_env(mock_relayer(), &[]),
+            HandleMsg::ChangeOrdersToProcessing {
+                order_positions: vec![Uint128(0)],
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(mock_relayer(), &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock_azero_transaction_hash".to_string(),
+                }],
+            },
         )
+        .unwrap();
+        match from_binary(&relayer_orders(&deps).unwrap()).unwrap() {
+            QueryAnswer::RelayerOrders { orders, .. } => assert_eq!(orders[0].status, 2),
+            _ => panic!("Unexpected"),
+        }
     }
 
     #[test]
-    fn test_fill_orders() {
-        let azero_transaction_hash: String = "mock_azero_transaction_hash".to_string();
+    fn test_admin_operations() {
         let (_init_result, mut deps) = init_helper(true);
-        let mut handle_msg = HandleMsg::FillOrders {
-            fill_details: vec![],
+        let env = mock_env(MOCK_ADMIN, &[]);
+        let operation = AdminOperation::UpdateExecutionFee {
+            execution_fee: Uint128(1),
         };
+        let pending_admin_operations =
+            |deps: &Extern<MockStorage, MockApi, MockQuerier>| match from_binary(
+                &query(deps, QueryMsg::PendingAdminOperations {}).unwrap(),
+            )
+            .unwrap()
+            {
+                QueryAnswer::PendingAdminOperations {
+                    pending_admin_operations,
+                    delay,
+                } => {
+                    assert_eq!(delay, ADMIN_OPERATION_DELAY);
+                    pending_admin_operations
+                }
+                _ => panic!("Unexpected"),
+            };
 
-        // = when not called by an admin
-        let mut handle_result = handle(
-            &mut deps,
-            mock_env(mock_contract().address, &[]),
-            handle_msg.clone(),
-        );
-        // = * it raises an error
+        // = when queued by a non-admin
+        // = * it raises an Unauthorized error
         assert_eq!(
-            handle_result.unwrap_err(),
+            handle(
+                &mut deps,
+                mock_env(mock_user_address(), &[]),
+                HandleMsg::QueueAdminOperation {
+                    operation: operation.clone(),
+                },
+            )
+            .unwrap_err(),
             StdError::Unauthorized { backtrace: None }
         );
 
-        // = when called by an admin
-        // == when order in fill_details does not exist
-        handle_msg = HandleMsg::FillOrders {
-            fill_details: vec![
-                FillDetail {
-                    position: cosmwasm_std::Uint128(0),
-                    azero_transaction_hash: azero_transaction_hash.clone(),
-                },
-                FillDetail {
-                    position: cosmwasm_std::Uint128(1),
-                    azero_transaction_hash: azero_transaction_hash,
-                },
-            ],
+        // = when queued by the admin
+        let handle_result = handle(
+            &mut deps,
+            env.clone(),
+            HandleMsg::QueueAdminOperation {
+                operation: operation.clone(),
+            },
+        );
+        // = * it sends back the pending operation, executable once the delay has passed
+        let pending_admin_operation = PendingAdminOperation {
+            id: Uint128(0),
+            operation: operation.clone(),
+            queued_at_block_time: env.block.time,
+            executable_at_block_time: env.block.time + ADMIN_OPERATION_DELAY,
         };
-        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        // == * it raises an error
+        match from_binary(&handle_result.unwrap().data.unwrap()).unwrap() {
+            HandleAnswer::QueueAdminOperation {
+                pending_admin_operation: answer,
+            } => assert_eq!(answer, pending_admin_operation),
+            _ => panic!("Unexpected"),
+        }
+        // = * it lists the operation as pending
         assert_eq!(
-            handle_result.unwrap_err(),
-            NotFound {
-                kind: "cw_secret_network_butt_migration::state::Order".to_string(),
-                backtrace: None
-            }
+            pending_admin_operations(&deps),
+            vec![pending_admin_operation.clone()]
         );
 
-        // == when order in fill_details exists
-        create_order_helper(&mut deps);
-        create_order_helper(&mut deps);
-        // === when order in fill_details is not processing (0/2/3)
-        // ==== * it does not set the order status to filled
-        // ==== * it does not send that order's butt to mount doom
-        // ==== * it does not send that order's execution fee to the user
-        // ==== * it does not increase butt sent to mount doom in config by that order's amount
-        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        let mut handle_result_unwrapped = handle_result.unwrap();
-        let mut creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            1,
-        )
-        .unwrap();
-        let mut contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-            1,
+        // = when executed before the delay has passed
+        // = * it raises an error
+        let mut early_env = mock_env(MOCK_ADMIN, &[]);
+        early_env.block.time += ADMIN_OPERATION_DELAY - 1;
+        assert_eq!(
+            handle(
+                &mut deps,
+                early_env,
+                HandleMsg::ExecuteAdminOperation {
+                    id: Uint128(0),
+                    key: None,
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err(format!(
+                "Admin operation can't be executed until {}.",
+                pending_admin_operation.executable_at_block_time
+            ))
+        );
+
+        // = when cancelled
+        let mut executable_env = mock_env(MOCK_ADMIN, &[]);
+        executable_env.block.time += ADMIN_OPERATION_DELAY;
+        // == by a non-admin
+        // == * it raises an Unauthorized error
+        assert_eq!(
+            handle(
+                &mut deps,
+                mock_env(mock_user_address(), &[]),
+                HandleMsg::CancelAdminOperation { id: Uint128(0) },
+            )
+            .unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
+        // == by the admin
+        handle(
+            &mut deps,
+            env.clone(),
+            HandleMsg::CancelAdminOperation { id: Uint128(0) },
         )
         .unwrap();
-        assert_eq!(creator_order.status, 0);
-        assert_eq!(contract_order.status, 0);
-        let mut config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-        assert_eq!(handle_result_unwrapped.messages, vec![]);
-        assert_eq!(config.total_sent_to_mount_doom, Uint128(0));
+        // == * it removes the operation so that it can't be executed
+        assert_eq!(pending_admin_operations(&deps), vec![]);
+        assert_eq!(
+            handle(
+                &mut deps,
+                executable_env.clone(),
+                HandleMsg::ExecuteAdminOperation {
+                    id: Uint128(0),
+                    key: None,
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err("Admin operation not found.")
+        );
 
-        // === when order in fill_details is processing (1)
-        creator_order.execution_fee = Some(cosmwasm_std::Uint128(1));
-        creator_order.status = 1;
-        update_creator_order_and_associated_contract_order(
-            &mut deps.storage,
-            creator_order.clone(),
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
+        // = when an execution fee update is executed after the delay
+        execute_admin_operation_helper(&mut deps, operation, None).unwrap();
+        // = * it updates the execution fee and removes the operation
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.execution_fee, Uint128(1));
+        assert_eq!(pending_admin_operations(&deps), vec![]);
+
+        // = when Mount Doom is changed without a queued operation
+        // = * it raises an error
+        let new_mount_doom = SecretContract {
+            address: HumanAddr::from("new-mount-doom-address"),
+            contract_hash: "new-mount-doom-contract-hash".to_string(),
+        };
+        assert_eq!(
+            handle(
+                &mut deps,
+                env.clone(),
+                HandleMsg::UpdateRegisteredToken {
+                    token_address: mock_butt().address,
+                    enabled: true,
+                    minimum_amount: Uint128(0),
+                    mount_doom: Some(new_mount_doom.clone()),
+                    rate_limit: None,
+                    address_quota: None,
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err("Mount Doom can only be changed with a queued admin operation.")
+        );
+
+        // = when a Mount Doom change is queued for a token that isn't registered
+        // = * it raises an error
+        assert_eq!(
+            handle(
+                &mut deps,
+                env.clone(),
+                HandleMsg::QueueAdminOperation {
+                    operation: AdminOperation::ChangeMountDoom {
+                        token_address: mock_contract().address,
+                        mount_doom: new_mount_doom.clone(),
+                    },
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err("Token is not registered.")
+        );
+
+        // = when a Mount Doom change for BUTT is executed after the delay
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::ChangeMountDoom {
+                token_address: mock_butt().address,
+                mount_doom: new_mount_doom.clone(),
+            },
+            None,
         )
         .unwrap();
+        // = * it updates Mount Doom for the token and in the config
+        let butt: RegisteredToken = registered_token(
+            &deps.storage,
+            &deps.api.canonical_address(&mock_butt().address).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(butt.mount_doom, Some(new_mount_doom.clone()));
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.mount_doom, new_mount_doom);
 
-        // ==== * it sets the order status to filled (2) for both user and contract
-        // ==== * it sends butt to mount doom
-        // ==== * it increases butt sent to mount doom in config
-        // ==== * it does not send that order's execution fee to the user
-        handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        creator_order = order_at_position(
-            &mut deps.storage,
-            &deps.api.canonical_address(&mock_user_address()).unwrap(),
-            1,
+        // = when a burn_tokens update is executed after the delay
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::UpdateBurnTokens { burn_tokens: true },
+            None,
         )
         .unwrap();
-        contract_order = order_at_position(
-            &mut deps.storage,
-            &deps
-                .api
-                .canonical_address(&mock_contract().address)
-                .unwrap(),
-            1,
+        // = * it updates burn_tokens
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert!(config.burn_tokens);
+
+        // = when a fee recipients update without recipients is queued
+        // = * it raises an error
+        assert_eq!(
+            handle(
+                &mut deps,
+                env.clone(),
+                HandleMsg::QueueAdminOperation {
+                    operation: AdminOperation::UpdateFeeRecipients {
+                        fee_recipients: vec![],
+                    },
+                },
+            )
+            .unwrap_err(),
+            StdError::generic_err(
+                "Fee recipients must be present and have weights greater than zero."
+            )
+        );
+
+        // = when a fee recipients update is executed after the delay
+        let fee_recipients: Vec<FeeRecipient> = vec![
+            FeeRecipient {
+                address: mock_user_address(),
+                weight: 2,
+            },
+            FeeRecipient {
+                address: HumanAddr::from(MOCK_ADMIN),
+                weight: 1,
+            },
+        ];
+        execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::UpdateFeeRecipients {
+                fee_recipients: fee_recipients.clone(),
+            },
+            None,
         )
         .unwrap();
-        assert_eq!(creator_order.status, 2);
-        assert_eq!(contract_order.status, 2);
-        handle_result_unwrapped = handle_result.unwrap();
+        // = * it updates the fee recipients
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.fee_recipients, fee_recipients);
+
+        // = when an admin change is executed after the delay
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::ChangeAdmin {
+                admin: mock_user_address(),
+            },
+            None,
+        );
+        // = * it logs the operation
         assert_eq!(
-            handle_result_unwrapped.messages,
+            handle_result.unwrap().log,
             vec![
-                snip20::transfer_msg(
-                    config.mount_doom.address.clone(),
-                    creator_order.amount,
-                    None,
-                    BLOCK_SIZE,
-                    config.butt.contract_hash,
-                    config.butt.address,
-                )
-                .unwrap(),
-                snip20::transfer_msg(
-                    config.admin,
-                    creator_order.execution_fee.unwrap(),
-                    None,
-                    BLOCK_SIZE,
-                    config.sscrt.contract_hash,
-                    config.sscrt.address,
-                )
-                .unwrap()
+                log("action", "execute_admin_operation"),
+                log("id", 5),
+                log("operation", "change_admin"),
             ]
         );
-        config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-        assert_eq!(config.total_sent_to_mount_doom, contract_order.amount);
+        // = * it transfers admin
+        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
+        assert_eq!(config.admin, mock_user_address());
+        assert_eq!(
+            handle(
+                &mut deps,
+                env,
+                HandleMsg::QueueAdminOperation {
+                    operation: AdminOperation::UpdateExecutionFee {
+                        execution_fee: Uint128(2),
+                    },
+                },
+            )
+            .unwrap_err(),
+            StdError::Unauthorized { backtrace: None }
+        );
     }
 
     #[test]
-    fn test_register_tokens() {
-        let (_init_result, mut deps) = init_helper(false);
+    fn test_freeze() {
+        let mut deps = mock_dependencies(
+            20,
+            &[Coin {
+                denom: SCRT_DENOM.to_string(),
+                amount: Uint128(1_000),
+            }],
+        );
+        init(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            InitMsg {
+                allowlist_enabled: None,
+                burn_tokens: None,
+                butt: mock_butt(),
+                execution_fee: mock_execution_fee(),
+                mount_doom: mock_mount_doom(),
+                sscrt: mock_sscrt(),
+                transfer_memos_enabled: None,
+                relayer: None,
+                importing: None,
+            },
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::RegisterTokens {
+                tokens: vec![mock_butt(), mock_token()],
+                viewing_key: MOCK_VIEWING_KEY.to_string(),
+            },
+        )
+        .unwrap();
+        create_order_helper(&mut deps);
+        let new_instance: HumanAddr = HumanAddr::from("new-instance");
 
-        // When tokens are in the parameter
-        let handle_msg = HandleMsg::RegisterTokens {
-            tokens: vec![mock_butt(), mock_token()],
-            viewing_key: MOCK_VIEWING_KEY.to_string(),
-        };
-        // = when called by a non-admin
+        // = when queued by a non-admin
         // = * it raises an Unauthorized error
         let handle_result = handle(
             &mut deps,
             mock_env(mock_user_address(), &[]),
-            handle_msg.clone(),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::Freeze {
+                    new_instance: new_instance.clone(),
+                },
+            },
         );
         assert_eq!(
             handle_result.unwrap_err(),
             StdError::Unauthorized { backtrace: None }
         );
-
-        // = when called by the admin
-        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        let handle_result_unwrapped = handle_result.unwrap();
-        // == when tokens are not registered
-        // == * it stores the registered tokens
+        // = when the new instance is the contract itself
+        // = * it raises an error
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::Freeze {
+                    new_instance: mock_contract().address,
+                },
+            },
+        );
         assert_eq!(
-            read_registered_token(
-                &deps.storage,
-                &deps.api.canonical_address(&mock_butt().address).unwrap()
-            )
-            .is_some(),
-            true
+            handle_result.unwrap_err(),
+            StdError::generic_err("New instance must be another contract.")
+        );
+        // = when executed without a key
+        // = * it raises an error
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::Freeze {
+                new_instance: new_instance.clone(),
+            },
+            None,
         );
         assert_eq!(
-            read_registered_token(
-                &deps.storage,
-                &deps.api.canonical_address(&mock_token().address).unwrap()
-            )
-            .is_some(),
-            true
+            handle_result.unwrap_err(),
+            StdError::generic_err("Key is required to move tokens.")
         );
+        assert!(!read_frozen(&deps.storage));
 
-        // == * it registers the contract with the tokens
-        // == * it sets the viewing key for the contract with the tokens
+        // = when executed with a key
+        let handle_result = execute_admin_operation_helper(
+            &mut deps,
+            AdminOperation::Freeze {
+                new_instance: new_instance.clone(),
+            },
+            Some(MOCK_VIEWING_KEY.to_string()),
+        );
+        // = * it moves the SCRT and registered token balances to the new instance
+        let handle_result_unwrapped: HandleResponse = handle_result.unwrap();
+        let coins: Vec<Coin> = vec![Coin {
+            denom: SCRT_DENOM.to_string(),
+            amount: Uint128(1_000),
+        }];
         assert_eq!(
             handle_result_unwrapped.messages,
             vec![
-                snip20::register_receive_msg(
-                    mock_contract().contract_hash.clone(),
-                    None,
-                    BLOCK_SIZE,
-                    mock_butt().contract_hash,
-                    mock_butt().address,
-                )
-                .unwrap(),
-                snip20::set_viewing_key_msg(
-                    MOCK_VIEWING_KEY.to_string(),
+                CosmosMsg::Bank(BankMsg::Send {
+                    from_address: mock_contract().address,
+                    to_address: new_instance.clone(),
+                    amount: coins.clone(),
+                }),
+                snip20::transfer_msg(
+                    new_instance.clone(),
+                    Uint128(MOCK_AMOUNT),
                     None,
                     BLOCK_SIZE,
                     mock_butt().contract_hash,
                     mock_butt().address,
                 )
                 .unwrap(),
-                snip20::register_receive_msg(
-                    mock_contract().contract_hash,
+                snip20::transfer_msg(
+                    new_instance.clone(),
+                    Uint128(MOCK_AMOUNT),
                     None,
                     BLOCK_SIZE,
                     mock_token().contract_hash,
                     mock_token().address,
                 )
                 .unwrap(),
-                snip20::set_viewing_key_msg(
-                    MOCK_VIEWING_KEY.to_string(),
-                    None,
-                    BLOCK_SIZE,
-                    mock_token().contract_hash,
-                    mock_token().address,
-                )
-                .unwrap()
             ]
         );
-
-        // === context when tokens are registered
-        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg);
-        let handle_result_unwrapped = handle_result.unwrap();
-        // === * it sets the viewing key for the contract with the tokens
-        assert_eq!(
-            handle_result_unwrapped.messages,
-            vec![
-                snip20::set_viewing_key_msg(
-                    MOCK_VIEWING_KEY.to_string(),
-                    None,
-                    BLOCK_SIZE,
-                    mock_butt().contract_hash,
-                    mock_butt().address,
-                )
+        match from_binary(&handle_result_unwrapped.data.unwrap()).unwrap() {
+            HandleAnswer::Freeze {
+                coins: moved_coins,
+                tokens,
+            } => {
+                assert_eq!(moved_coins, coins);
+                assert_eq!(
+                    tokens,
+                    vec![
+                        TokenAmount {
+                            token: mock_butt().address,
+                            amount: Uint128(MOCK_AMOUNT),
+                        },
+                        TokenAmount {
+                            token: mock_token().address,
+                            amount: Uint128(MOCK_AMOUNT),
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Unexpected"),
+        }
+        // = * it rejects tokens, order changes and admin operations from then on
+        let handle_result = handle(
+            &mut deps,
+            mock_env(mock_butt().address, &[]),
+            HandleMsg::Receive {
+                sender: mock_user_address(),
+                from: mock_user_address(),
+                amount: Uint128(MOCK_AMOUNT),
+                msg: to_binary(&ReceiveMsg::CreateOrder {
+                    to: mock_token().address,
+                    fee_waiver_code: None,
+                    owner: None,
+                    memo: None,
+                })
                 .unwrap(),
-                snip20::set_viewing_key_msg(
-                    MOCK_VIEWING_KEY.to_string(),
-                    None,
-                    BLOCK_SIZE,
-                    mock_token().contract_hash,
-                    mock_token().address,
-                )
-                .unwrap()
-            ]
+            },
+        );
+        assert_eq!(
+            handle_result.unwrap_err(),
+            StdError::generic_err("Contract is frozen.")
         );
-    }
-
-    #[test]
-    fn test_rescue_tokens() {
-        let (_init_result, mut deps) = init_helper(true);
-        let handle_msg = HandleMsg::RescueTokens {
-            denom: Some("uscrt".to_string()),
-            key: Some(MOCK_VIEWING_KEY.to_string()),
-            token_address: Some(mock_butt().address),
-        };
-        // = when called by a non-admin
-        // = * it raises an Unauthorized error
         let handle_result = handle(
             &mut deps,
             mock_env(mock_user_address(), &[]),
-            handle_msg.clone(),
+            HandleMsg::CancelOrder {
+                position: Uint128(0),
+            },
         );
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::Unauthorized { backtrace: None }
+            StdError::generic_err("Contract is frozen.")
         );
-
-        // = when called by the admin
-        // == when only denom is specified
-        let handle_msg = HandleMsg::RescueTokens {
-            denom: Some("uscrt".to_string()),
-            key: None,
-            token_address: None,
-        };
-        // === when the contract does not have the coin in it
-        // === * it sends a transfer with the balance of the coin for the contract
-        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        let handle_result_unwrapped = handle_result.unwrap();
-        assert_eq!(
-            handle_result_unwrapped.messages,
-            vec![CosmosMsg::Bank(BankMsg::Send {
-                from_address: mock_contract().address,
-                to_address: HumanAddr(MOCK_ADMIN.to_string()),
-                amount: vec![Coin {
-                    denom: "uscrt".to_string(),
-                    amount: Uint128(0)
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::FillOrders {
+                fill_details: vec![FillDetail {
+                    position: Uint128(0),
+                    amount: None,
+                    azero_transaction_hash: "mock-azero-transaction-hash".to_string(),
                 }],
-            })]
+            },
         );
-        // == when only token address and key are specified
-        // === when token address is BUTT
-        // === * it raises an error
-        let handle_msg = HandleMsg::RescueTokens {
-            denom: None,
-            key: Some(MOCK_VIEWING_KEY.to_string()),
-            token_address: Some(mock_butt().address),
-        };
-        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::generic_err("BUTT can't be rescued.")
+            StdError::generic_err("Contract is frozen.")
         );
-        // === when token address isn't BUTT
-        // === * it sends the excess amount of token
-        let handle_msg = HandleMsg::RescueTokens {
-            denom: None,
-            key: Some(MOCK_VIEWING_KEY.to_string()),
-            token_address: Some(mock_token().address),
-        };
-        let handle_result = handle(&mut deps, mock_env(MOCK_ADMIN, &[]), handle_msg.clone());
-        let handle_result_unwrapped = handle_result.unwrap();
-        assert_eq!(
-            handle_result_unwrapped.messages,
-            vec![snip20::transfer_msg(
-                HumanAddr::from(MOCK_ADMIN),
-                Uint128(MOCK_AMOUNT),
-                None,
-                BLOCK_SIZE,
-                mock_token().contract_hash,
-                mock_token().address,
-            )
-            .unwrap()]
+        let handle_result = handle(
+            &mut deps,
+            mock_env(MOCK_ADMIN, &[]),
+            HandleMsg::QueueAdminOperation {
+                operation: AdminOperation::UpdateExecutionFee {
+                    execution_fee: Uint128(1),
+                },
+            },
         );
-    }
-
-    #[test]
-    fn test_update_config() {
-        let (_init_result, mut deps) = init_helper(false);
-        let handle_msg = HandleMsg::UpdateConfig {
-            execution_fee: Uint128(MOCK_AMOUNT),
-        };
-        let env = mock_env(mock_user_address(), &[]);
-        // = when called by a non-admin
-        // = * it raises an Unauthorized error
-        let handle_result = handle(&mut deps, env.clone(), handle_msg.clone());
         assert_eq!(
             handle_result.unwrap_err(),
-            StdError::Unauthorized { backtrace: None }
+            StdError::generic_err("Contract is frozen.")
         );
-
-        // = when called by the admin
-        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-        assert_eq!(config.execution_fee, mock_execution_fee());
-        handle(
-            &mut deps,
-            mock_env(HumanAddr::from(MOCK_ADMIN), &[]),
-            handle_msg,
-        )
-        .unwrap();
-        let config: Config = TypedStore::attach(&deps.storage).load(CONFIG_KEY).unwrap();
-        // = * it updates the execution_fee
-        assert_eq!(config.execution_fee, Uint128(MOCK_AMOUNT))
     }
 }
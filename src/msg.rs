@@ -1,28 +1,105 @@
-use crate::state::{FillDetail, HumanizedOrder, SecretContract};
-use cosmwasm_std::{Binary, HumanAddr, Uint128};
+use crate::state::{
+    AccruedFee, AddressQuota, AdminOperation, Config, ExportedState, FeeWaiver, FeeWaiverCode,
+    FillDetail, HumanizedOrder, Order, OrderDestination, OrderFilter, OrderSplit,
+    PendingAdminOperation, RateLimit, RegisteredToken, RelayerOrder, SecretContract, TokenAmount,
+    TokenStats,
+};
+use cosmwasm_std::{Binary, Coin, HumanAddr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
+    pub allowlist_enabled: Option<bool>,
+    pub burn_tokens: Option<bool>,
     pub butt: SecretContract,
     pub mount_doom: SecretContract,
     pub execution_fee: Uint128,
     pub sscrt: SecretContract,
+    pub transfer_memos_enabled: Option<bool>,
+    pub relayer: Option<HumanAddr>,
+    pub importing: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    ChangeOrdersToProcessing {
+        orders: Vec<RelayerOrder>,
+    },
+    // Fees accrued are totals for each token, before they're split between fee recipients
+    FillOrders {
+        orders: Vec<RelayerOrder>,
+        burned: Vec<TokenAmount>,
+        sent_to_mount_doom: Vec<TokenAmount>,
+        fees_accrued: Vec<AccruedFee>,
+    },
+    // SCRT and tokens moved to the new instance
+    Freeze {
+        coins: Vec<Coin>,
+        tokens: Vec<TokenAmount>,
+    },
+    QueueAdminOperation {
+        pending_admin_operation: PendingAdminOperation,
+    },
+    RegisterTokens {
+        registered_tokens: Vec<HumanAddr>,
+    },
+    RescueTokens {
+        coins: Vec<Coin>,
+        tokens: Vec<TokenAmount>,
+    },
+    UpdateConfig {
+        config: Config,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
+    AddFeeWaiverCodes {
+        fee_waiver_codes: Vec<FeeWaiverCode>,
+    },
+    AddFeeWaivers {
+        fee_waivers: Vec<FeeWaiver>,
+    },
+    AddToAllowlist {
+        addresses: Vec<HumanAddr>,
+    },
+    CancelAdminOperation {
+        id: Uint128,
+    },
     CancelOrder {
         position: Uint128,
     },
     ChangeOrdersToProcessing {
         order_positions: Vec<Uint128>,
     },
+    // The key is the contract's viewing key for the token, needed to rescue tokens
+    ExecuteAdminOperation {
+        id: Uint128,
+        key: Option<String>,
+    },
     FillOrders {
         fill_details: Vec<FillDetail>,
     },
+    FinaliseImport {},
+    ImportDestinations {
+        destinations: Vec<OrderDestination>,
+    },
+    ImportState {
+        config: Option<Config>,
+        registered_tokens: Vec<RegisteredToken>,
+        state: Option<ExportedState>,
+        orders: Vec<Order>,
+        checksum: Binary,
+    },
+    MigrateState {
+        limit: Uint128,
+    },
+    QueueAdminOperation {
+        operation: AdminOperation,
+    },
     Receive {
         sender: HumanAddr,
         from: HumanAddr,
@@ -33,40 +110,168 @@ pub enum HandleMsg {
         tokens: Vec<SecretContract>,
         viewing_key: String,
     },
-    RescueTokens {
-        denom: Option<String>,
-        key: Option<String>,
-        token_address: Option<HumanAddr>,
+    RemoveFeeWaiverCodes {
+        code_hashes: Vec<Binary>,
+    },
+    RemoveFeeWaivers {
+        addresses: Vec<HumanAddr>,
+    },
+    RemoveFromAllowlist {
+        addresses: Vec<HumanAddr>,
+    },
+    SetExecutionFeeForOrder {
+        owner: Option<HumanAddr>,
     },
     UpdateConfig {
-        execution_fee: Uint128,
+        allowlist_enabled: Option<bool>,
+        transfer_memos_enabled: Option<bool>,
     },
+    UpdateOrderDestination {
+        position: Uint128,
+        to: HumanAddr,
+    },
+    UpdateRegisteredToken {
+        token_address: HumanAddr,
+        enabled: bool,
+        minimum_amount: Uint128,
+        mount_doom: Option<SecretContract>,
+        rate_limit: Option<RateLimit>,
+        address_quota: Option<AddressQuota>,
+    },
+    WithdrawFees {},
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryAnswer {
+    AccruedFees {
+        accrued_fees: Vec<AccruedFee>,
+    },
+    ContractVersion {
+        version: u8,
+    },
+    ExecutionFee {
+        execution_fee: Uint128,
+        token_execution_fee: Option<Uint128>,
+    },
+    ExportState {
+        config: Config,
+        contract_version: u8,
+        registered_tokens: Vec<RegisteredToken>,
+        state: ExportedState,
+        orders: Vec<Order>,
+        total: Uint128,
+        checksum: Binary,
+    },
+    FilteredOrders {
+        orders: Vec<HumanizedOrder>,
+        next_cursor: Option<Uint128>,
+    },
+    MigrationStats {
+        open_orders: Uint128,
+        execution_fee: Uint128,
+        tokens: Vec<TokenStats>,
+    },
+    OrderHistory {
+        orders: Vec<HumanizedOrder>,
+        next_cursor: Option<Uint128>,
+    },
     Orders {
         orders: Vec<HumanizedOrder>,
         total: Option<Uint128>,
     },
+    PendingAdminOperations {
+        pending_admin_operations: Vec<PendingAdminOperation>,
+        delay: u64,
+    },
+    RelayerOrders {
+        orders: Vec<RelayerOrder>,
+        next_cursor: Option<Uint128>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    AccruedFees {
+        address: HumanAddr,
+    },
     Config {},
+    ContractVersion {},
+    ExecutionFee {
+        token_address: HumanAddr,
+        amount: Uint128,
+    },
+    ExportState {
+        key: String,
+        start: Uint128,
+        limit: Uint128,
+        checksum: Option<Binary>,
+    },
+    // Newest first unless ascending. Pass next_cursor from the previous response as start_after
+    // to continue, until next_cursor is None.
+    FilteredOrders {
+        address: HumanAddr,
+        key: String,
+        filter: OrderFilter,
+        ascending: Option<bool>,
+        start_after: Option<Uint128>,
+        limit: Uint128,
+    },
+    MigrationStats {},
+    // Newest first. Pass next_cursor from the previous response as start_after to continue,
+    // until next_cursor is None. Orders created while paging don't shift later pages.
+    OrderHistory {
+        address: HumanAddr,
+        key: String,
+        start_after: Option<Uint128>,
+        limit: Uint128,
+    },
     Orders {
         address: HumanAddr,
         key: String,
         page: Uint128,
         page_size: Uint128,
     },
+    PendingAdminOperations {},
+    RegisteredToken {
+        token_address: HumanAddr,
+    },
+    // Contract copies of orders for the relayer, authenticated with the relayer's viewing key
+    // for butt. Paged like FilteredOrders.
+    RelayerOrders {
+        key: String,
+        filter: OrderFilter,
+        ascending: Option<bool>,
+        start_after: Option<Uint128>,
+        limit: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
-    SetExecutionFeeForOrder {},
-    CreateOrder { to: HumanAddr },
+    SetExecutionFeeForOrder {
+        owner: Option<HumanAddr>,
+    },
+    CreateOrder {
+        to: HumanAddr,
+        fee_waiver_code: Option<String>,
+        owner: Option<HumanAddr>,
+        memo: Option<String>,
+    },
+    CreateOrders {
+        splits: Vec<OrderSplit>,
+    },
+}
+
+// SNIP-20 handle messages that the version of secret-toolkit used can't build
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Snip20HandleMsg {
+    Transfer {
+        recipient: HumanAddr,
+        amount: Uint128,
+        memo: Option<String>,
+    },
 }
@@ -1,5 +1,13 @@
-use crate::constants::PREFIX_REGISTERED_TOKENS;
-use cosmwasm_std::{Api, CanonicalAddr, HumanAddr, StdResult, Storage, Uint128};
+use crate::constants::{
+    ACCRUED_FEE_RECIPIENTS_KEY, ADMIN_OPERATIONS_COUNT_KEY, ALLOWLISTED_ADDRESSES_KEY,
+    CONTRACT_VERSION_KEY, FEE_WAIVER_ADDRESSES_KEY, FEE_WAIVER_CODE_HASHES_KEY, FROZEN_KEY,
+    IMPORTING_KEY, OPEN_ORDERS_COUNT_KEY, PENDING_ADMIN_OPERATIONS_KEY, PREFIX_ACCRUED_FEES,
+    PREFIX_ADDRESS_QUOTA_USAGE, PREFIX_ALLOWLIST, PREFIX_FEE_WAIVERS, PREFIX_FEE_WAIVER_CODES,
+    PREFIX_REGISTERED_TOKENS, RATE_LIMIT_BUCKETS, REGISTERED_TOKEN_ADDRESSES_KEY,
+};
+use cosmwasm_std::{
+    Api, Binary, CanonicalAddr, HumanAddr, ReadonlyStorage, StdResult, Storage, Uint128,
+};
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 use schemars::JsonSchema;
 use secret_toolkit::storage::{TypedStore, TypedStoreMut};
@@ -8,30 +16,669 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub admin: HumanAddr,
+    pub allowlist_enabled: bool,
+    pub burn_tokens: bool,
     pub butt: SecretContract,
     pub mount_doom: SecretContract,
     pub execution_fee: Uint128,
+    pub fee_recipients: Vec<FeeRecipient>,
+    pub scrt_execution_fees_held: Uint128,
     pub sscrt: SecretContract,
-    pub total_sent_to_mount_doom: Uint128,
+    pub transfer_memos_enabled: bool,
+    pub relayer: Option<HumanAddr>,
+}
+
+impl Config {
+    // Addresses that can process and fill orders
+    pub fn operators(&self) -> Vec<HumanAddr> {
+        let mut operators: Vec<HumanAddr> = vec![self.admin.clone()];
+        if let Some(relayer) = &self.relayer {
+            operators.push(relayer.clone());
+        }
+        operators
+    }
+}
+
+// Maximum amount an address can migrate, within each rolling period or over its lifetime
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct AddressQuota {
+    pub amount: Uint128,
+    pub period: Option<RateLimitPeriod>,
+}
+
+// Execution fee for orders with an amount greater than or equal to minimum_amount
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct ExecutionFeeTier {
+    pub minimum_amount: Uint128,
+    pub execution_fee: Uint128,
 }
 
+// State other than the config, registered tokens and orders that is moved to a new instance.
+// Fee waiver codes are only known by their hashes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportedState {
+    pub accrued_fees: Vec<FeeRecipientAccruedFees>,
+    pub admin_operations_count: Uint128,
+    pub allowlist: Vec<HumanAddr>,
+    pub fee_waiver_codes: Vec<FeeWaiverCode>,
+    pub fee_waivers: Vec<FeeWaiver>,
+    pub pending_admin_operations: Vec<PendingAdminOperation>,
+}
+
+// Execution fees are split between fee recipients in proportion to their weights
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct FeeRecipient {
+    pub address: HumanAddr,
+    pub weight: u64,
+}
+
+// Discount on the execution fee, in basis points, for orders created by address.
+// A discount of 10000 waives the execution fee.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct FeeWaiver {
+    pub address: HumanAddr,
+    pub discount_basis_points: u16,
+}
+
+// Discount on the execution fee for the one order created with the code that hashes to code_hash
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct FeeWaiverCode {
+    pub code_hash: Binary,
+    pub discount_basis_points: u16,
+}
+
+// A fill of part or all of an order's amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Fill {
+    pub amount: Uint128,
+    pub azero_transaction_hash: String,
+}
+
+// When amount is not set, the rest of the order's amount is filled
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FillDetail {
     pub position: Uint128,
+    pub amount: Option<Uint128>,
     pub azero_transaction_hash: String,
 }
 
+// Destination of the order at position in the contract's orders
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderDestination {
+    pub position: Uint128,
+    pub to: HumanAddr,
+}
+
+// Orders match when they match every filter that is set.
+// Created after and before are exclusive and minimum amount is inclusive.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct OrderFilter {
+    pub statuses: Option<Vec<u8>>,
+    pub created_after_block_height: Option<u64>,
+    pub created_before_block_height: Option<u64>,
+    pub created_after_block_time: Option<u64>,
+    pub created_before_block_time: Option<u64>,
+    pub minimum_amount: Option<Uint128>,
+}
+impl OrderFilter {
+    pub fn matches(&self, order: &Order) -> bool {
+        if let Some(statuses) = &self.statuses {
+            if !statuses.contains(&order.status) {
+                return false;
+            }
+        }
+        if let Some(height) = self.created_after_block_height {
+            if order.created_at_block_height <= height {
+                return false;
+            }
+        }
+        if let Some(height) = self.created_before_block_height {
+            if order.created_at_block_height >= height {
+                return false;
+            }
+        }
+        if let Some(time) = self.created_after_block_time {
+            if order.created_at_block_time <= time {
+                return false;
+            }
+        }
+        if let Some(time) = self.created_before_block_time {
+            if order.created_at_block_time >= time {
+                return false;
+            }
+        }
+        if let Some(minimum_amount) = self.minimum_amount {
+            if order.amount < minimum_amount {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+// Part of a send to be ordered to its own destination
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderSplit {
+    pub to: HumanAddr,
+    pub amount: Uint128,
+}
+
+// Execution fee taken from the order amount, in basis points, bounded by minimum and maximum
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct PercentageExecutionFee {
+    pub basis_points: u16,
+    pub minimum: Uint128,
+    pub maximum: Uint128,
+}
+impl PercentageExecutionFee {
+    pub fn execution_fee(&self, amount: Uint128) -> Uint128 {
+        let execution_fee: Uint128 = amount.multiply_ratio(self.basis_points, 10_000u128);
+        if execution_fee < self.minimum {
+            self.minimum
+        } else if execution_fee > self.maximum {
+            self.maximum
+        } else {
+            execution_fee
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitPeriod {
+    Blocks(u64),
+    Seconds(u64),
+}
+// Usage is counted over any rolling period by splitting periods into RATE_LIMIT_BUCKETS buckets.
+// Every bucket that overlaps the period ending at the current block counts towards the limit.
+impl RateLimitPeriod {
+    pub fn add(&self, buckets: &mut Vec<RateLimitBucket>, now: u64, amount: Uint128) {
+        let start: u64 = self.bucket_start(now);
+        match buckets.iter_mut().find(|bucket| bucket.start == start) {
+            Some(bucket) => bucket.amount += amount,
+            None => buckets.push(RateLimitBucket { start, amount }),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.length() == 0
+    }
+
+    // Block height or time, depending on the unit of the period
+    pub fn now(&self, block_height: u64, block_time: u64) -> u64 {
+        match self {
+            RateLimitPeriod::Blocks(_) => block_height,
+            RateLimitPeriod::Seconds(_) => block_time,
+        }
+    }
+
+    // Drops the buckets that end before the period ending at now and returns the amount in the rest
+    pub fn prune(&self, buckets: &mut Vec<RateLimitBucket>, now: u64) -> Uint128 {
+        let period_start: u64 = (now + 1).saturating_sub(self.length());
+        let bucket_size: u64 = self.bucket_size();
+        buckets.retain(|bucket| bucket.start + bucket_size > period_start);
+        buckets
+            .iter()
+            .fold(Uint128(0), |total, bucket| total + bucket.amount)
+    }
+
+    // Releases an amount added at added_at, if its bucket still counts towards the limit
+    pub fn release(&self, buckets: &mut Vec<RateLimitBucket>, added_at: u64, amount: Uint128) {
+        let start: u64 = self.bucket_start(added_at);
+        if let Some(bucket) = buckets.iter_mut().find(|bucket| bucket.start == start) {
+            bucket.amount = Uint128(bucket.amount.u128().saturating_sub(amount.u128()));
+        }
+    }
+
+    fn bucket_size(&self) -> u64 {
+        (self.length() / RATE_LIMIT_BUCKETS).max(1)
+    }
+
+    fn bucket_start(&self, at: u64) -> u64 {
+        at - at % self.bucket_size()
+    }
+
+    fn length(&self) -> u64 {
+        match self {
+            RateLimitPeriod::Blocks(blocks) => *blocks,
+            RateLimitPeriod::Seconds(seconds) => *seconds,
+        }
+    }
+}
+
+// Maximum amount that can be migrated within any rolling period
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct RateLimit {
+    pub amount: Uint128,
+    pub period: RateLimitPeriod,
+}
+
+// Amount migrated from the block height or time start, until the next bucket
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct RateLimitBucket {
+    pub start: u64,
+    pub amount: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
 pub struct SecretContract {
     pub address: HumanAddr,
     pub contract_hash: String,
 }
 
+// Aggregate figures for a registered token that don't identify anyone
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct TokenStats {
+    pub token: HumanAddr,
+    pub enabled: bool,
+    pub total_migrated: Uint128,
+    pub total_burned: Uint128,
+    pub total_sent_to_mount_doom: Uint128,
+    pub minimum_amount: Uint128,
+    pub execution_fee_tiers: Vec<ExecutionFeeTier>,
+    pub percentage_execution_fee: Option<PercentageExecutionFee>,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct TokenAmount {
+    pub token: HumanAddr,
+    pub amount: Uint128,
+}
+
+// === Accrued fees ===
+// Execution fees earned by a fee recipient that haven't been withdrawn yet. A token of None is SCRT.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct AccruedFee {
+    pub token: Option<HumanAddr>,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct FeeRecipientAccruedFees {
+    pub fee_recipient: HumanAddr,
+    pub accrued_fees: Vec<AccruedFee>,
+}
+
+pub fn read_accrued_fee_recipients<S: ReadonlyStorage>(storage: &S) -> Vec<CanonicalAddr> {
+    read_storage_keys(storage, ACCRUED_FEE_RECIPIENTS_KEY)
+        .into_iter()
+        .map(|key| CanonicalAddr(Binary(key)))
+        .collect()
+}
+
+pub fn read_accrued_fees<S: Storage>(
+    storage: &S,
+    fee_recipient: &CanonicalAddr,
+) -> StdResult<Vec<AccruedFee>> {
+    let accrued_fees_storage = ReadonlyPrefixedStorage::new(PREFIX_ACCRUED_FEES, storage);
+    let accrued_fees_storage = TypedStore::<Vec<AccruedFee>, _>::attach(&accrued_fees_storage);
+    Ok(accrued_fees_storage
+        .may_load(fee_recipient.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn remove_accrued_fees<S: Storage>(
+    storage: &mut S,
+    fee_recipient: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut accrued_fees_storage = PrefixedStorage::new(PREFIX_ACCRUED_FEES, storage);
+    accrued_fees_storage.remove(fee_recipient.as_slice());
+    remove_storage_key(
+        storage,
+        ACCRUED_FEE_RECIPIENTS_KEY,
+        fee_recipient.as_slice(),
+    )
+}
+
+pub fn write_accrued_fees<S: Storage>(
+    storage: &mut S,
+    fee_recipient: &CanonicalAddr,
+    accrued_fees: &[AccruedFee],
+) -> StdResult<()> {
+    let mut accrued_fees_storage = PrefixedStorage::new(PREFIX_ACCRUED_FEES, storage);
+    let mut accrued_fees_storage =
+        TypedStoreMut::<Vec<AccruedFee>, _>::attach(&mut accrued_fees_storage);
+    accrued_fees_storage.store(fee_recipient.as_slice(), &accrued_fees.to_vec())?;
+    add_storage_key(
+        storage,
+        ACCRUED_FEE_RECIPIENTS_KEY,
+        fee_recipient.as_slice(),
+    )
+}
+
+// === Address quota usage ===
+// Amount of a token ordered by an address and not cancelled, kept so that quotas can be checked
+// without scanning the address's orders. Buckets are kept for the period of the token's quota
+// and are reset when that period changes.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct AddressQuotaUsage {
+    pub total: Uint128,
+    pub period: Option<RateLimitPeriod>,
+    pub buckets: Vec<RateLimitBucket>,
+}
+
+pub fn read_address_quota_usage<S: ReadonlyStorage>(
+    storage: &S,
+    address: &CanonicalAddr,
+    token: &CanonicalAddr,
+) -> AddressQuotaUsage {
+    let usage_storage = ReadonlyPrefixedStorage::multilevel(
+        &[PREFIX_ADDRESS_QUOTA_USAGE, address.as_slice()],
+        storage,
+    );
+    TypedStore::<AddressQuotaUsage, _>::attach(&usage_storage)
+        .may_load(token.as_slice())
+        .unwrap()
+        .unwrap_or(AddressQuotaUsage {
+            total: Uint128(0),
+            period: None,
+            buckets: vec![],
+        })
+}
+
+pub fn write_address_quota_usage<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    token: &CanonicalAddr,
+    usage: &AddressQuotaUsage,
+) -> StdResult<()> {
+    let mut usage_storage =
+        PrefixedStorage::multilevel(&[PREFIX_ADDRESS_QUOTA_USAGE, address.as_slice()], storage);
+    TypedStoreMut::<AddressQuotaUsage, _>::attach(&mut usage_storage).store(token.as_slice(), usage)
+}
+
+// === Allowlist ===
+pub fn read_allowlisted_addresses<S: ReadonlyStorage>(storage: &S) -> Vec<CanonicalAddr> {
+    read_storage_keys(storage, ALLOWLISTED_ADDRESSES_KEY)
+        .into_iter()
+        .map(|key| CanonicalAddr(Binary(key)))
+        .collect()
+}
+
+pub fn read_allowlisted<S: Storage>(storage: &S, address: &CanonicalAddr) -> bool {
+    let allowlist_storage = ReadonlyPrefixedStorage::new(PREFIX_ALLOWLIST, storage);
+    let allowlist_storage = TypedStore::<bool, _>::attach(&allowlist_storage);
+    allowlist_storage
+        .may_load(address.as_slice())
+        .unwrap()
+        .unwrap_or(false)
+}
+
+pub fn remove_allowlisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) -> StdResult<()> {
+    let mut allowlist_storage = PrefixedStorage::new(PREFIX_ALLOWLIST, storage);
+    allowlist_storage.remove(address.as_slice());
+    remove_storage_key(storage, ALLOWLISTED_ADDRESSES_KEY, address.as_slice())
+}
+
+pub fn write_allowlisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) -> StdResult<()> {
+    let mut allowlist_storage = PrefixedStorage::new(PREFIX_ALLOWLIST, storage);
+    let mut allowlist_storage = TypedStoreMut::<bool, _>::attach(&mut allowlist_storage);
+    allowlist_storage.store(address.as_slice(), &true)?;
+    add_storage_key(storage, ALLOWLISTED_ADDRESSES_KEY, address.as_slice())
+}
+
+// === Contract version ===
+// Contracts deployed before the version was stored are version 1
+pub fn read_contract_version<S: ReadonlyStorage>(storage: &S) -> u8 {
+    TypedStore::<u8, _>::attach(storage)
+        .may_load(CONTRACT_VERSION_KEY)
+        .unwrap()
+        .unwrap_or(1)
+}
+
+pub fn write_contract_version<S: Storage>(storage: &mut S, version: u8) -> StdResult<()> {
+    TypedStoreMut::<u8, _>::attach(storage).store(CONTRACT_VERSION_KEY, &version)
+}
+
+// === Fee waivers ===
+pub fn read_fee_waiver_addresses<S: ReadonlyStorage>(storage: &S) -> Vec<CanonicalAddr> {
+    read_storage_keys(storage, FEE_WAIVER_ADDRESSES_KEY)
+        .into_iter()
+        .map(|key| CanonicalAddr(Binary(key)))
+        .collect()
+}
+
+pub fn read_fee_waiver<S: Storage>(storage: &S, address: &CanonicalAddr) -> Option<u16> {
+    let fee_waivers_storage = ReadonlyPrefixedStorage::new(PREFIX_FEE_WAIVERS, storage);
+    let fee_waivers_storage = TypedStore::<u16, _>::attach(&fee_waivers_storage);
+    fee_waivers_storage.may_load(address.as_slice()).unwrap()
+}
+
+pub fn remove_fee_waiver<S: Storage>(storage: &mut S, address: &CanonicalAddr) -> StdResult<()> {
+    let mut fee_waivers_storage = PrefixedStorage::new(PREFIX_FEE_WAIVERS, storage);
+    fee_waivers_storage.remove(address.as_slice());
+    remove_storage_key(storage, FEE_WAIVER_ADDRESSES_KEY, address.as_slice())
+}
+
+pub fn write_fee_waiver<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    discount_basis_points: u16,
+) -> StdResult<()> {
+    let mut fee_waivers_storage = PrefixedStorage::new(PREFIX_FEE_WAIVERS, storage);
+    let mut fee_waivers_storage = TypedStoreMut::<u16, _>::attach(&mut fee_waivers_storage);
+    fee_waivers_storage.store(address.as_slice(), &discount_basis_points)?;
+    add_storage_key(storage, FEE_WAIVER_ADDRESSES_KEY, address.as_slice())
+}
+
+pub fn read_fee_waiver_code_hashes<S: ReadonlyStorage>(storage: &S) -> Vec<Vec<u8>> {
+    read_storage_keys(storage, FEE_WAIVER_CODE_HASHES_KEY)
+}
+
+pub fn read_fee_waiver_code<S: Storage>(storage: &S, code_hash: &[u8]) -> Option<u16> {
+    let fee_waiver_codes_storage = ReadonlyPrefixedStorage::new(PREFIX_FEE_WAIVER_CODES, storage);
+    let fee_waiver_codes_storage = TypedStore::<u16, _>::attach(&fee_waiver_codes_storage);
+    fee_waiver_codes_storage.may_load(code_hash).unwrap()
+}
+
+pub fn remove_fee_waiver_code<S: Storage>(storage: &mut S, code_hash: &[u8]) -> StdResult<()> {
+    let mut fee_waiver_codes_storage = PrefixedStorage::new(PREFIX_FEE_WAIVER_CODES, storage);
+    fee_waiver_codes_storage.remove(code_hash);
+    remove_storage_key(storage, FEE_WAIVER_CODE_HASHES_KEY, code_hash)
+}
+
+pub fn write_fee_waiver_code<S: Storage>(
+    storage: &mut S,
+    code_hash: &[u8],
+    discount_basis_points: u16,
+) -> StdResult<()> {
+    let mut fee_waiver_codes_storage = PrefixedStorage::new(PREFIX_FEE_WAIVER_CODES, storage);
+    let mut fee_waiver_codes_storage =
+        TypedStoreMut::<u16, _>::attach(&mut fee_waiver_codes_storage);
+    fee_waiver_codes_storage.store(code_hash, &discount_basis_points)?;
+    add_storage_key(storage, FEE_WAIVER_CODE_HASHES_KEY, code_hash)
+}
+
+// === Frozen ===
+// Contracts are frozen for good once their escrow has been moved to a new instance
+pub fn read_frozen<S: ReadonlyStorage>(storage: &S) -> bool {
+    TypedStore::<bool, _>::attach(storage)
+        .may_load(FROZEN_KEY)
+        .unwrap()
+        .unwrap_or(false)
+}
+
+pub fn write_frozen<S: Storage>(storage: &mut S) -> StdResult<()> {
+    TypedStoreMut::<bool, _>::attach(storage).store(FROZEN_KEY, &true)
+}
+
+// === Importing ===
+// Instances initialised to import state can't be used until the import is finalised,
+// after which state can't be imported again
+pub fn read_importing<S: ReadonlyStorage>(storage: &S) -> bool {
+    TypedStore::<bool, _>::attach(storage)
+        .may_load(IMPORTING_KEY)
+        .unwrap()
+        .unwrap_or(false)
+}
+
+pub fn write_importing<S: Storage>(storage: &mut S, importing: bool) -> StdResult<()> {
+    TypedStoreMut::<bool, _>::attach(storage).store(IMPORTING_KEY, &importing)
+}
+
+// === Open orders count ===
+// Number of orders with a status of open, kept so that it can be shown without a scan
+pub fn read_open_orders_count<S: ReadonlyStorage>(storage: &S) -> u128 {
+    TypedStore::<u128, _>::attach(storage)
+        .may_load(OPEN_ORDERS_COUNT_KEY)
+        .unwrap()
+        .unwrap_or(0)
+}
+
+pub fn write_open_orders_count<S: Storage>(storage: &mut S, count: u128) -> StdResult<()> {
+    TypedStoreMut::<u128, _>::attach(storage).store(OPEN_ORDERS_COUNT_KEY, &count)
+}
+
+// === Pending admin operations ===
+// Sensitive admin operations can only be executed once ADMIN_OPERATION_DELAY has passed since
+// they were queued, so that users get notice of them. They can be cancelled until then.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminOperation {
+    ChangeAdmin {
+        admin: HumanAddr,
+    },
+    ChangeMountDoom {
+        token_address: HumanAddr,
+        mount_doom: SecretContract,
+    },
+    // A relayer of None leaves order processing to the admin
+    ChangeRelayer {
+        relayer: Option<HumanAddr>,
+    },
+    // Freezes the contract for good and moves its escrow and fees to new_instance
+    Freeze {
+        new_instance: HumanAddr,
+    },
+    RescueTokens {
+        denom: Option<String>,
+        token_address: Option<HumanAddr>,
+    },
+    UpdateBurnTokens {
+        burn_tokens: bool,
+    },
+    UpdateExecutionFee {
+        execution_fee: Uint128,
+    },
+    UpdateExecutionFeeTiers {
+        token_address: HumanAddr,
+        execution_fee_tiers: Vec<ExecutionFeeTier>,
+    },
+    UpdateFeeRecipients {
+        fee_recipients: Vec<FeeRecipient>,
+    },
+    UpdatePercentageExecutionFee {
+        token_address: HumanAddr,
+        percentage_execution_fee: Option<PercentageExecutionFee>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdminOperation {
+    pub id: Uint128,
+    pub operation: AdminOperation,
+    pub queued_at_block_time: u64,
+    pub executable_at_block_time: u64,
+}
+
+// Ids aren't reused, so the count includes operations that have been executed or cancelled
+pub fn read_admin_operations_count<S: ReadonlyStorage>(storage: &S) -> u128 {
+    TypedStore::<u128, _>::attach(storage)
+        .may_load(ADMIN_OPERATIONS_COUNT_KEY)
+        .unwrap()
+        .unwrap_or(0)
+}
+
+pub fn write_admin_operations_count<S: Storage>(storage: &mut S, count: u128) -> StdResult<()> {
+    TypedStoreMut::<u128, _>::attach(storage).store(ADMIN_OPERATIONS_COUNT_KEY, &count)
+}
+
+pub fn read_pending_admin_operations<S: ReadonlyStorage>(
+    storage: &S,
+) -> Vec<PendingAdminOperation> {
+    TypedStore::<Vec<PendingAdminOperation>, _>::attach(storage)
+        .may_load(PENDING_ADMIN_OPERATIONS_KEY)
+        .unwrap()
+        .unwrap_or_default()
+}
+
+pub fn write_pending_admin_operations<S: Storage>(
+    storage: &mut S,
+    pending_admin_operations: &[PendingAdminOperation],
+) -> StdResult<()> {
+    TypedStoreMut::<Vec<PendingAdminOperation>, _>::attach(storage).store(
+        PENDING_ADMIN_OPERATIONS_KEY,
+        &pending_admin_operations.to_vec(),
+    )
+}
+
 // === Registered tokens ===
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
 pub struct RegisteredToken {
     pub address: HumanAddr,
+    pub address_quota: Option<AddressQuota>,
     pub contract_hash: String,
+    pub enabled: bool,
+    pub execution_fee_tiers: Vec<ExecutionFeeTier>,
+    pub minimum_amount: Uint128,
+    pub mount_doom: Option<SecretContract>,
+    pub percentage_execution_fee: Option<PercentageExecutionFee>,
+    pub rate_limit: Option<RateLimit>,
+    pub rate_limit_buckets: Vec<RateLimitBucket>,
+    pub total_burned: Uint128,
+    pub total_sent_to_mount_doom: Uint128,
+}
+impl RegisteredToken {
+    // Tiers are stored in ascending order of minimum amount, so the last tier that the amount
+    // meets applies. The default execution fee applies when no tier is met.
+    pub fn execution_fee(&self, default_execution_fee: Uint128, amount: Uint128) -> Uint128 {
+        self.execution_fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.minimum_amount)
+            .map(|tier| tier.execution_fee)
+            .unwrap_or(default_execution_fee)
+    }
+
+    pub fn migratable(&self) -> bool {
+        self.enabled && self.mount_doom.is_some()
+    }
+
+    pub fn stats(&self) -> TokenStats {
+        TokenStats {
+            token: self.address.clone(),
+            enabled: self.enabled,
+            total_migrated: self.total_burned + self.total_sent_to_mount_doom,
+            total_burned: self.total_burned,
+            total_sent_to_mount_doom: self.total_sent_to_mount_doom,
+            minimum_amount: self.minimum_amount,
+            execution_fee_tiers: self.execution_fee_tiers.clone(),
+            percentage_execution_fee: self.percentage_execution_fee.clone(),
+        }
+    }
+}
+
+// Registered tokens can't be iterated over in storage, so their addresses are also kept in a list
+pub fn append_registered_token_address<S: Storage>(
+    storage: &mut S,
+    token_address: &HumanAddr,
+) -> StdResult<()> {
+    let mut token_addresses: Vec<HumanAddr> = read_registered_token_addresses(storage);
+    if !token_addresses.contains(token_address) {
+        token_addresses.push(token_address.clone());
+        TypedStoreMut::<Vec<HumanAddr>, _>::attach(storage)
+            .store(REGISTERED_TOKEN_ADDRESSES_KEY, &token_addresses)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_registered_token_addresses<S: ReadonlyStorage>(storage: &S) -> Vec<HumanAddr> {
+    TypedStore::<Vec<HumanAddr>, _>::attach(storage)
+        .may_load(REGISTERED_TOKEN_ADDRESSES_KEY)
+        .unwrap()
+        .unwrap_or_default()
 }
 
 pub fn read_registered_token<S: Storage>(
@@ -55,46 +702,273 @@ pub fn write_registered_token<S: Storage>(
     registered_tokens_storage.store(token_address.as_slice(), registered_token)
 }
 
+// === Storage keys ===
+// Prefixed storage can't be iterated over, so the keys of entries that are exported are also
+// kept in a list
+fn add_storage_key<S: Storage>(storage: &mut S, list_key: &[u8], key: &[u8]) -> StdResult<()> {
+    let mut keys: Vec<Vec<u8>> = read_storage_keys(storage, list_key);
+    if !keys.iter().any(|stored_key| stored_key.as_slice() == key) {
+        keys.push(key.to_vec());
+        TypedStoreMut::<Vec<Vec<u8>>, _>::attach(storage).store(list_key, &keys)?;
+    }
+
+    Ok(())
+}
+
+fn read_storage_keys<S: ReadonlyStorage>(storage: &S, list_key: &[u8]) -> Vec<Vec<u8>> {
+    TypedStore::<Vec<Vec<u8>>, _>::attach(storage)
+        .may_load(list_key)
+        .unwrap()
+        .unwrap_or_default()
+}
+
+fn remove_storage_key<S: Storage>(storage: &mut S, list_key: &[u8], key: &[u8]) -> StdResult<()> {
+    let mut keys: Vec<Vec<u8>> = read_storage_keys(storage, list_key);
+    keys.retain(|stored_key| stored_key.as_slice() != key);
+    TypedStoreMut::<Vec<Vec<u8>>, _>::attach(storage).store(list_key, &keys)
+}
+
 // === Orders ===
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct HumanizedOrder {
     pub position: Uint128,
     pub execution_fee: Option<Uint128>,
+    pub execution_fee_in_scrt: bool,
+    pub token_execution_fee: Option<Uint128>,
+    pub fee_discount_basis_points: u16,
+    pub fee_exempt: bool,
     pub creator: HumanAddr,
+    pub sender: HumanAddr,
+    pub token: HumanAddr,
     pub amount: Uint128,
+    pub filled_amount: Uint128,
+    pub fills: Vec<Fill>,
     pub to: HumanAddr,
+    pub memo: Option<String>,
     pub status: u8,
     pub azero_transaction_hash: Option<String>,
     pub created_at_block_time: u64,
     pub created_at_block_height: u64,
 }
 
+// What the relayer needs to fill a contract order. The creator, memo and fees are left out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelayerOrder {
+    pub position: Uint128,
+    pub token: HumanAddr,
+    pub amount: Uint128,
+    pub filled_amount: Uint128,
+    pub fills: Vec<Fill>,
+    pub to: HumanAddr,
+    pub status: u8,
+    pub created_at_block_time: u64,
+    pub created_at_block_height: u64,
+}
+
 // activity (0 => open, 1 => filled, 2 => cancelled)
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
 pub struct Order {
     pub position: Uint128,
     pub execution_fee: Option<Uint128>,
+    pub execution_fee_in_scrt: bool,
+    pub token_execution_fee: Option<Uint128>,
+    pub fee_discount_basis_points: u16,
+    pub fee_exempt: bool,
     pub other_storage_position: Uint128,
     pub creator: CanonicalAddr,
+    // Address that sent the tokens, which differs from the creator when created on their behalf
+    pub sender: CanonicalAddr,
+    pub token: HumanAddr,
     pub amount: Uint128,
+    pub filled_amount: Uint128,
+    pub fills: Vec<Fill>,
     pub to: HumanAddr,
+    pub memo: Option<String>,
     pub status: u8,
     pub azero_transaction_hash: Option<String>,
     pub created_at_block_time: u64,
     pub created_at_block_height: u64,
 }
 impl Order {
+    // Portion of an amount owed for the fills between filled_amount_before and filled_amount.
+    // Working from the totals filled means the portions add up to the amount once filled.
+    pub fn pro_rated(&self, amount: Uint128, filled_amount_before: Uint128) -> Uint128 {
+        if self.amount.is_zero() {
+            return amount;
+        }
+
+        Uint128(
+            amount
+                .multiply_ratio(self.filled_amount, self.amount)
+                .u128()
+                - amount
+                    .multiply_ratio(filled_amount_before, self.amount)
+                    .u128(),
+        )
+    }
+
     pub fn into_humanized<A: Api>(self, api: &A) -> StdResult<HumanizedOrder> {
         Ok(HumanizedOrder {
             position: self.position,
             execution_fee: self.execution_fee,
+            execution_fee_in_scrt: self.execution_fee_in_scrt,
+            token_execution_fee: self.token_execution_fee,
+            fee_discount_basis_points: self.fee_discount_basis_points,
+            fee_exempt: self.fee_exempt,
             creator: api.human_address(&self.creator)?,
+            sender: api.human_address(&self.sender)?,
+            token: self.token,
             amount: self.amount,
+            filled_amount: self.filled_amount,
+            fills: self.fills,
             to: self.to,
+            memo: self.memo,
             status: self.status,
             azero_transaction_hash: self.azero_transaction_hash,
             created_at_block_time: self.created_at_block_time,
             created_at_block_height: self.created_at_block_height,
         })
     }
+
+    pub fn into_relayer_order(self) -> RelayerOrder {
+        RelayerOrder {
+            position: self.position,
+            token: self.token,
+            amount: self.amount,
+            filled_amount: self.filled_amount,
+            fills: self.fills,
+            to: self.to,
+            status: self.status,
+            created_at_block_time: self.created_at_block_time,
+            created_at_block_height: self.created_at_block_height,
+        }
+    }
+}
+
+// === Version 1 ===
+// Storage layouts of version 1, kept so that stored state can be upgraded
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV1 {
+    pub admin: HumanAddr,
+    pub butt: SecretContract,
+    pub mount_doom: SecretContract,
+    pub execution_fee: Uint128,
+    pub sscrt: SecretContract,
+    pub total_sent_to_mount_doom: Uint128,
+}
+impl ConfigV1 {
+    pub fn upgrade(self) -> Config {
+        Config {
+            admin: self.admin.clone(),
+            allowlist_enabled: false,
+            burn_tokens: false,
+            butt: self.butt,
+            mount_doom: self.mount_doom,
+            execution_fee: self.execution_fee,
+            fee_recipients: vec![FeeRecipient {
+                address: self.admin,
+                weight: 1,
+            }],
+            scrt_execution_fees_held: Uint128(0),
+            sscrt: self.sscrt,
+            transfer_memos_enabled: true,
+            relayer: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderV1 {
+    pub position: Uint128,
+    pub execution_fee: Option<Uint128>,
+    pub other_storage_position: Uint128,
+    pub creator: CanonicalAddr,
+    pub amount: Uint128,
+    pub to: HumanAddr,
+    pub status: u8,
+    pub azero_transaction_hash: Option<String>,
+    pub created_at_block_time: u64,
+    pub created_at_block_height: u64,
+}
+impl OrderV1 {
+    // Version 1 only migrated BUTT and filled orders all at once
+    pub fn upgrade(self, butt: &HumanAddr) -> Order {
+        let fills: Vec<Fill> = match (self.status, self.azero_transaction_hash.clone()) {
+            (2, Some(azero_transaction_hash)) => vec![Fill {
+                amount: self.amount,
+                azero_transaction_hash,
+            }],
+            _ => vec![],
+        };
+        Order {
+            position: self.position,
+            execution_fee: self.execution_fee,
+            execution_fee_in_scrt: false,
+            token_execution_fee: None,
+            fee_discount_basis_points: 0,
+            fee_exempt: false,
+            other_storage_position: self.other_storage_position,
+            creator: self.creator.clone(),
+            sender: self.creator,
+            token: butt.clone(),
+            amount: self.amount,
+            filled_amount: if self.status == 2 {
+                self.amount
+            } else {
+                Uint128(0)
+            },
+            fills,
+            to: self.to,
+            memo: None,
+            status: self.status,
+            azero_transaction_hash: self.azero_transaction_hash,
+            created_at_block_time: self.created_at_block_time,
+            created_at_block_height: self.created_at_block_height,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
+pub struct RegisteredTokenV1 {
+    pub address: HumanAddr,
+    pub contract_hash: String,
+}
+impl RegisteredTokenV1 {
+    pub fn read<S: Storage>(
+        storage: &S,
+        token_address: &CanonicalAddr,
+    ) -> StdResult<Option<RegisteredTokenV1>> {
+        let registered_tokens_storage =
+            ReadonlyPrefixedStorage::new(PREFIX_REGISTERED_TOKENS, storage);
+        let registered_tokens_storage =
+            TypedStore::<RegisteredTokenV1, _>::attach(&registered_tokens_storage);
+        registered_tokens_storage.may_load(token_address.as_slice())
+    }
+
+    // Only BUTT was migratable in version 1
+    pub fn upgrade(self, config: &ConfigV1) -> RegisteredToken {
+        let butt: bool = self.address == config.butt.address;
+        RegisteredToken {
+            address: self.address,
+            address_quota: None,
+            contract_hash: self.contract_hash,
+            enabled: butt,
+            execution_fee_tiers: vec![],
+            minimum_amount: Uint128(0),
+            mount_doom: if butt {
+                Some(config.mount_doom.clone())
+            } else {
+                None
+            },
+            percentage_execution_fee: None,
+            rate_limit: None,
+            rate_limit_buckets: vec![],
+            total_burned: Uint128(0),
+            total_sent_to_mount_doom: if butt {
+                config.total_sent_to_mount_doom
+            } else {
+                Uint128(0)
+            },
+        }
+    }
 }
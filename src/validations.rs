@@ -1,3 +1,4 @@
+use crate::constants::{MAX_MEMO_LENGTH, MAX_PAGE_SIZE};
 use cosmwasm_std::{HumanAddr, StdError, StdResult, Uint128};
 
 pub fn authorize(allowed: Vec<HumanAddr>, received: &HumanAddr) -> StdResult<()> {
@@ -8,6 +9,16 @@ pub fn authorize(allowed: Vec<HumanAddr>, received: &HumanAddr) -> StdResult<()>
     Ok(())
 }
 
+pub fn validate_destination(to: &HumanAddr) -> StdResult<()> {
+    if to.as_str().trim().is_empty() {
+        return Err(StdError::generic_err(
+            "Destination address must be present.",
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn validate_human_addr(
     expected: &HumanAddr,
     received: &HumanAddr,
@@ -20,6 +31,30 @@ pub fn validate_human_addr(
     Ok(())
 }
 
+pub fn validate_memo(memo: &Option<String>) -> StdResult<()> {
+    if let Some(memo_unwrapped) = memo {
+        if memo_unwrapped.len() > MAX_MEMO_LENGTH {
+            return Err(StdError::generic_err(format!(
+                "Memo must be {} bytes or less.",
+                MAX_MEMO_LENGTH
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_page_size(page_size: u128) -> StdResult<()> {
+    if page_size == 0 || page_size > MAX_PAGE_SIZE {
+        return Err(StdError::generic_err(format!(
+            "Page size must be between 1 and {}.",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    Ok(())
+}
+
 pub fn validate_uint128(expected: Uint128, received: Uint128, message: &str) -> StdResult<()> {
     if expected != received {
         return Err(StdError::generic_err(message));